@@ -0,0 +1,208 @@
+use crate::commands::metadata::{hashed_gallery, image_hash, image_hashes};
+use crate::models::{Album, GeneratedImage, UpdateAlbumPayload};
+use chrono::Utc;
+use std::collections::HashMap;
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+use uuid::Uuid;
+
+const ALBUMS_STORE: &str = "albums.json";
+
+fn load_albums(app: &AppHandle) -> Result<Vec<Album>, String> {
+    let store = app.store(ALBUMS_STORE).map_err(|e| e.to_string())?;
+    match store.get("albums") {
+        Some(val) => serde_json::from_value(val).map_err(|e| e.to_string()),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn save_albums(app: &AppHandle, albums: &[Album]) -> Result<(), String> {
+    let store = app.store(ALBUMS_STORE).map_err(|e| e.to_string())?;
+    store.set("albums", serde_json::json!(albums));
+    store.save().map_err(|e| e.to_string())
+}
+
+/// Applies `apply` to the album with `id` and persists the result.
+fn modify_album<F>(app: &AppHandle, id: &str, apply: F) -> Result<Album, String>
+where
+    F: FnOnce(&mut Album) -> Result<(), String>,
+{
+    let mut albums = load_albums(app)?;
+    let album = albums
+        .iter_mut()
+        .find(|a| a.id == id)
+        .ok_or("Album not found")?;
+
+    apply(album)?;
+    album.updated_at = Utc::now().timestamp_millis();
+    let updated = album.clone();
+
+    save_albums(app, &albums)?;
+    Ok(updated)
+}
+
+fn hashes_for(app: &AppHandle, paths: &[String]) -> Result<Vec<String>, String> {
+    image_hashes(app, paths)?.into_iter().collect()
+}
+
+#[tauri::command]
+pub async fn create_album(
+    app: AppHandle,
+    name: String,
+    description: Option<String>,
+) -> Result<Album, String> {
+    let timestamp = Utc::now().timestamp_millis();
+    let album = Album {
+        id: Uuid::new_v4().to_string(),
+        name,
+        description,
+        images: Vec::new(),
+        cover: None,
+        created_at: timestamp,
+        updated_at: timestamp,
+    };
+
+    let mut albums = load_albums(&app)?;
+    albums.push(album.clone());
+    save_albums(&app, &albums)?;
+
+    Ok(album)
+}
+
+#[tauri::command]
+pub async fn list_albums(app: AppHandle) -> Result<Vec<Album>, String> {
+    load_albums(&app)
+}
+
+#[tauri::command]
+pub async fn update_album(app: AppHandle, payload: UpdateAlbumPayload) -> Result<Album, String> {
+    modify_album(&app, &payload.id, |album| {
+        if let Some(name) = payload.name {
+            album.name = name;
+        }
+        if let Some(desc) = payload.description {
+            album.description = Some(desc);
+        }
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub async fn delete_album(app: AppHandle, id: String) -> Result<(), String> {
+    let mut albums = load_albums(&app)?;
+    albums.retain(|a| a.id != id);
+    save_albums(&app, &albums)
+}
+
+#[tauri::command]
+pub async fn add_images_to_album(
+    app: AppHandle,
+    id: String,
+    paths: Vec<String>,
+) -> Result<Album, String> {
+    let hashes = hashes_for(&app, &paths)?;
+    modify_album(&app, &id, |album| {
+        for hash in hashes {
+            if !album.images.contains(&hash) {
+                album.images.push(hash);
+            }
+        }
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub async fn remove_images_from_album(
+    app: AppHandle,
+    id: String,
+    paths: Vec<String>,
+) -> Result<Album, String> {
+    let hashes = hashes_for(&app, &paths)?;
+    modify_album(&app, &id, |album| {
+        album.images.retain(|h| !hashes.contains(h));
+        if album
+            .cover
+            .as_ref()
+            .is_some_and(|cover| hashes.contains(cover))
+        {
+            album.cover = None;
+        }
+        Ok(())
+    })
+}
+
+/// Moves the given images to the front of the album in the given order.
+/// Members not mentioned keep their relative order after them.
+#[tauri::command]
+pub async fn reorder_album(
+    app: AppHandle,
+    id: String,
+    paths: Vec<String>,
+) -> Result<Album, String> {
+    let hashes = hashes_for(&app, &paths)?;
+    modify_album(&app, &id, |album| {
+        if let Some(unknown) = hashes.iter().find(|h| !album.images.contains(h)) {
+            return Err(format!("Image {} is not in this album", unknown));
+        }
+
+        let mut ordered: Vec<String> = Vec::new();
+        for hash in hashes {
+            if !ordered.contains(&hash) {
+                ordered.push(hash);
+            }
+        }
+        for hash in &album.images {
+            if !ordered.contains(hash) {
+                ordered.push(hash.clone());
+            }
+        }
+        album.images = ordered;
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub async fn set_album_cover(
+    app: AppHandle,
+    id: String,
+    path: Option<String>,
+) -> Result<Album, String> {
+    let hash = match path {
+        Some(p) => Some(image_hash(&app, &p)?),
+        None => None,
+    };
+    modify_album(&app, &id, |album| {
+        if let Some(hash) = &hash {
+            if !album.images.contains(hash) {
+                return Err("Cover image must be a member of the album".to_string());
+            }
+        }
+        album.cover = hash;
+        Ok(())
+    })
+}
+
+/// Resolves album members to their current gallery files.
+/// Members whose file is no longer in the output folder are omitted.
+pub(crate) fn album_images(app: &AppHandle, id: &str) -> Result<Vec<GeneratedImage>, String> {
+    let album = load_albums(app)?
+        .into_iter()
+        .find(|a| a.id == id)
+        .ok_or("Album not found")?;
+
+    let mut by_hash: HashMap<String, GeneratedImage> = HashMap::new();
+    for (hash, image) in hashed_gallery(app)? {
+        by_hash.entry(hash).or_insert(image);
+    }
+
+    Ok(album
+        .images
+        .iter()
+        .filter_map(|hash| by_hash.get(hash).cloned())
+        .collect())
+}
+
+#[tauri::command]
+pub async fn list_album_images(app: AppHandle, id: String) -> Result<Vec<GeneratedImage>, String> {
+    album_images(&app, &id)
+}
@@ -0,0 +1,311 @@
+use crate::commands::gallery::{resolve_output_dir, BATCHES_DIR};
+use crate::commands::generate::generate_into;
+use crate::db::{self, Database};
+use crate::models::{BatchRun, GeneratePayload, GenerationProgress, PromptContent};
+use crate::prompt;
+use chrono::{Local, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_dialog::{DialogExt, FilePath};
+use uuid::Uuid;
+
+const MANIFEST_NAME: &str = "manifest.json";
+const DEFAULT_SIZE: u32 = 1024;
+/// Bounds for row values, matching what the studio offers.
+const MAX_COUNT: u32 = 4;
+const MIN_SIZE: u32 = 64;
+const MAX_SIZE: u32 = 4096;
+/// Separator for list columns in CSV files.
+const CSV_LIST_SEPARATOR: char = ';';
+
+/// One row of a batch file. JSON files may use camelCase or snake_case keys.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+struct BatchRow {
+    prompt: String,
+    #[serde(alias = "negative_prompt")]
+    negative_prompt: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    count: Option<u32>,
+    #[serde(default, alias = "reference_images")]
+    reference_images: Vec<String>,
+    #[serde(default, alias = "resource_ids")]
+    resource_ids: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+enum RowStatus {
+    Pending,
+    Succeeded,
+    Failed,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+struct RowResult {
+    /// 1-based position in the batch file.
+    row: usize,
+    prompt: String,
+    status: RowStatus,
+    paths: Vec<String>,
+    error: Option<String>,
+}
+
+/// `manifest.json` in the batch folder, rewritten after every row so it stays
+/// accurate if the app quits midway.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+struct BatchManifest {
+    batch_id: String,
+    source: String,
+    started_at: i64,
+    finished_at: Option<i64>,
+    rows: Vec<RowResult>,
+}
+
+fn write_manifest(folder: &Path, manifest: &BatchManifest) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(manifest).map_err(|e| e.to_string())?;
+    fs::write(folder.join(MANIFEST_NAME), json).map_err(|e| e.to_string())
+}
+
+/// Converts CSV records into the JSON shape rows are parsed from: list columns
+/// split on `;` and numeric columns as numbers, so both formats share one parser.
+fn read_csv(path: &Path) -> Result<Vec<Value>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_path(path)
+        .map_err(|e| e.to_string())?;
+    let headers = reader.headers().map_err(|e| e.to_string())?.clone();
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| e.to_string())?;
+        let mut row = Map::new();
+        for (header, field) in headers.iter().zip(record.iter()) {
+            if field.is_empty() {
+                continue;
+            }
+            let value = match header {
+                "reference_images" | "referenceImages" | "resource_ids" | "resourceIds" => {
+                    Value::from(
+                        field
+                            .split(CSV_LIST_SEPARATOR)
+                            .map(str::trim)
+                            .filter(|s| !s.is_empty())
+                            .collect::<Vec<_>>(),
+                    )
+                }
+                "width" | "height" | "count" => field
+                    .parse::<u64>()
+                    .map(Value::from)
+                    .unwrap_or_else(|_| Value::from(field)),
+                _ => Value::from(field),
+            };
+            row.insert(header.to_string(), value);
+        }
+        rows.push(Value::Object(row));
+    }
+    Ok(rows)
+}
+
+fn read_rows(path: &Path) -> Result<Vec<Value>, String> {
+    let is_json = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    if !is_json {
+        return read_csv(path);
+    }
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    match serde_json::from_str(&content).map_err(|e| format!("Invalid batch file: {}", e))? {
+        Value::Array(rows) => Ok(rows),
+        _ => Err("Batch JSON must be an array of rows".to_string()),
+    }
+}
+
+fn validate_row(row: &BatchRow) -> Result<(), String> {
+    let count = row.count.unwrap_or(1);
+    if !(1..=MAX_COUNT).contains(&count) {
+        return Err(format!("count must be between 1 and {}", MAX_COUNT));
+    }
+    for (name, size) in [("width", row.width), ("height", row.height)] {
+        if !(MIN_SIZE..=MAX_SIZE).contains(&size.unwrap_or(DEFAULT_SIZE)) {
+            return Err(format!(
+                "{} must be between {} and {}",
+                name, MIN_SIZE, MAX_SIZE
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Builds the generation for a row. Resource IDs are expanded after the prompt
+/// like resource references in the studio, bringing their images along.
+/// Relative reference image paths are resolved against `base_dir`, the batch
+/// file's folder.
+fn row_payload(app: &AppHandle, row: BatchRow, base_dir: &Path) -> Result<GeneratePayload, String> {
+    validate_row(&row)?;
+    let mut content = vec![PromptContent::Text(row.prompt)];
+    content.extend(
+        row.resource_ids
+            .iter()
+            .map(|id| PromptContent::ResourceReference(id.clone())),
+    );
+    let rendered = {
+        let conn = app.state::<Database>().inner().lock()?;
+        // Rendering skips missing resources; a row naming one should fail instead
+        for id in &row.resource_ids {
+            if db::resources::get(&conn, id)?.is_none() {
+                return Err(format!("Resource not found: {}", id));
+            }
+        }
+        prompt::render::render(&conn, &content, &[])?
+    };
+
+    let mut reference_images: Vec<String> = row
+        .reference_images
+        .iter()
+        .map(|path| base_dir.join(path).to_string_lossy().to_string())
+        .collect();
+    let mut image_mapping = std::collections::HashMap::new();
+    for reference in rendered.references {
+        if !reference_images.contains(&reference.path) {
+            reference_images.push(reference.path.clone());
+        }
+        image_mapping.insert(reference.name, reference.path);
+    }
+
+    Ok(GeneratePayload {
+        prompt: rendered.prompt,
+        negative_prompt: row.negative_prompt,
+        width: row.width.unwrap_or(DEFAULT_SIZE),
+        height: row.height.unwrap_or(DEFAULT_SIZE),
+        count: row.count.unwrap_or(1),
+        reference_images: (!reference_images.is_empty()).then_some(reference_images),
+        image_mapping: (!image_mapping.is_empty()).then_some(image_mapping),
+        resource_ids: (!rendered.resource_ids.is_empty()).then_some(rendered.resource_ids),
+        variables: None,
+    })
+}
+
+async fn run_row(
+    app: &AppHandle,
+    row: Value,
+    base_dir: &Path,
+    folder: &Path,
+) -> Result<Vec<String>, String> {
+    let row: BatchRow = serde_json::from_value(row).map_err(|e| format!("Invalid row: {}", e))?;
+    let payload = row_payload(app, row, base_dir)?;
+    generate_into(app, payload, folder).await
+}
+
+/// Runs one generation per row of a CSV or JSON batch file in the background,
+/// saving into `Batches/{timestamp}_{batch id prefix}` under the output
+/// directory alongside a `manifest.json` of per-row results. The file is picked
+/// with a dialog unless `path` is given; returns `None` if the dialog is
+/// cancelled. Progress is reported through `generation-progress` events.
+#[tauri::command]
+pub async fn run_batch_file(
+    app: AppHandle,
+    path: Option<String>,
+) -> Result<Option<BatchRun>, String> {
+    let source: PathBuf = match path {
+        Some(path) => path.into(),
+        None => match app
+            .dialog()
+            .file()
+            .add_filter("Batch file", &["csv", "json"])
+            .blocking_pick_file()
+        {
+            Some(FilePath::Path(path)) => path,
+            _ => return Ok(None),
+        },
+    };
+
+    let rows = read_rows(&source)?;
+    if rows.is_empty() {
+        return Err("The batch file has no rows".to_string());
+    }
+
+    let batch_id = Uuid::new_v4().to_string();
+    let folder = resolve_output_dir(&app)?.join(BATCHES_DIR).join(format!(
+        "{}_{}",
+        Local::now().format("%Y%m%d_%H%M%S"),
+        &batch_id[..8]
+    ));
+    fs::create_dir_all(&folder).map_err(|e| e.to_string())?;
+
+    let mut manifest = BatchManifest {
+        batch_id: batch_id.clone(),
+        source: source.to_string_lossy().to_string(),
+        started_at: Utc::now().timestamp_millis(),
+        finished_at: None,
+        rows: rows
+            .iter()
+            .enumerate()
+            .map(|(i, row)| RowResult {
+                row: i + 1,
+                prompt: row
+                    .get("prompt")
+                    .and_then(|p| p.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+                status: RowStatus::Pending,
+                paths: Vec::new(),
+                error: None,
+            })
+            .collect(),
+    };
+    write_manifest(&folder, &manifest)?;
+
+    let run = BatchRun {
+        batch_id: batch_id.clone(),
+        folder: folder.to_string_lossy().to_string(),
+        rows: rows.len(),
+    };
+
+    let base_dir = source.parent().map(Path::to_path_buf).unwrap_or_default();
+    tauri::async_runtime::spawn(async move {
+        let total = rows.len();
+        for (i, row) in rows.into_iter().enumerate() {
+            let result = run_row(&app, row, &base_dir, &folder).await;
+            let entry = &mut manifest.rows[i];
+            match result {
+                Ok(paths) => {
+                    entry.status = RowStatus::Succeeded;
+                    entry.paths = paths;
+                }
+                Err(e) => {
+                    log::warn!("Batch row {} failed: {}", i + 1, e);
+                    entry.status = RowStatus::Failed;
+                    entry.error = Some(e);
+                }
+            }
+            if i + 1 == total {
+                manifest.finished_at = Some(Utc::now().timestamp_millis());
+            }
+            if let Err(e) = write_manifest(&folder, &manifest) {
+                log::warn!("Failed to write batch manifest: {}", e);
+            }
+
+            let entry = &manifest.rows[i];
+            let _ = app.emit(
+                "generation-progress",
+                GenerationProgress {
+                    batch_id: batch_id.clone(),
+                    current: i + 1,
+                    total,
+                    prompt: entry.prompt.clone(),
+                    paths: entry.paths.clone(),
+                    error: entry.error.clone(),
+                },
+            );
+        }
+    });
+
+    Ok(Some(run))
+}
@@ -0,0 +1,516 @@
+use crate::commands::resources::{resources_dir, unique_destination};
+use crate::commands::trash::{move_path, trash_resource, untrash_resource};
+use crate::commands::versions::{self, VERSIONS_DIR};
+use crate::db::{self, Database};
+use crate::imaging;
+use crate::models::{
+    BundleImportReport, ConflictStrategy, ImageImportFailure, Resource, TrashItem,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, State};
+use tauri_plugin_dialog::{DialogExt, FilePath};
+use uuid::Uuid;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+const BUNDLE_FORMAT: &str = "oneiria-resource-bundle";
+const BUNDLE_VERSION: u32 = 1;
+const MANIFEST_NAME: &str = "bundle.json";
+const BUNDLE_EXTENSION: &str = "oneiria";
+/// Upper bound on the uncompressed size of a single archive entry, so a
+/// crafted bundle cannot exhaust memory.
+const MAX_ENTRY_SIZE: u64 = 128 * 1024 * 1024;
+const MAX_MANIFEST_SIZE: u64 = 16 * 1024 * 1024;
+
+/// `bundle.json` at the root of a `.oneiria` archive. Image paths (in `images`
+/// and in prompt templates) are archive entry names of the form `{id}/{file}`.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BundleManifest {
+    format: String,
+    version: u32,
+    exported_at: i64,
+    resources: Vec<Resource>,
+}
+
+fn write_bundle(resources: &[Resource], dest: &Path) -> Result<(), String> {
+    let file = fs::File::create(dest).map_err(|e| e.to_string())?;
+    let mut zip = ZipWriter::new(file);
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+
+    let mut manifest = BundleManifest {
+        format: BUNDLE_FORMAT.to_string(),
+        version: BUNDLE_VERSION,
+        exported_at: Utc::now().timestamp_millis(),
+        resources: Vec::new(),
+    };
+
+    for resource in resources {
+        let mut mapping = HashMap::new();
+        let mut entries = Vec::new();
+        for image in &resource.images {
+            let path = Path::new(image);
+            let bytes = match fs::read(path) {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    log::warn!("Leaving {} out of the bundle: {}", image, e);
+                    continue;
+                }
+            };
+            let filename = path
+                .file_name()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_else(|| "image".to_string());
+            let mut entry = format!("{}/{}", resource.id, filename);
+            let mut n = 1;
+            while entries.contains(&entry) {
+                entry = format!("{}/{}_{}", resource.id, n, filename);
+                n += 1;
+            }
+
+            zip.start_file(entry.as_str(), stored)
+                .map_err(|e| e.to_string())?;
+            zip.write_all(&bytes).map_err(|e| e.to_string())?;
+            mapping.insert(image.clone(), entry.clone());
+            entries.push(entry);
+        }
+
+        manifest.resources.push(Resource {
+            prompt_template: resource.prompt_template.remap_images(&mapping),
+            images: entries,
+            ..resource.clone()
+        });
+    }
+
+    let json = serde_json::to_vec_pretty(&manifest).map_err(|e| e.to_string())?;
+    zip.start_file(MANIFEST_NAME, SimpleFileOptions::default())
+        .map_err(|e| e.to_string())?;
+    zip.write_all(&json).map_err(|e| e.to_string())?;
+    zip.finish().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Reads a whole archive entry, refusing entries larger than `limit` whatever
+/// size their header declares.
+fn read_entry(
+    archive: &mut ZipArchive<fs::File>,
+    name: &str,
+    limit: u64,
+) -> Result<Vec<u8>, String> {
+    let entry = archive
+        .by_name(name)
+        .map_err(|_| format!("{} is missing from the bundle", name))?;
+    if entry.size() > limit {
+        return Err(format!("{} is too large", name));
+    }
+    let mut bytes = Vec::new();
+    entry
+        .take(limit + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|e| e.to_string())?;
+    if bytes.len() as u64 > limit {
+        return Err(format!("{} is too large", name));
+    }
+    Ok(bytes)
+}
+
+fn read_manifest(archive: &mut ZipArchive<fs::File>) -> Result<BundleManifest, String> {
+    let json = read_entry(archive, MANIFEST_NAME, MAX_MANIFEST_SIZE)
+        .map_err(|e| format!("Not a resource bundle: {}", e))?;
+
+    let manifest: BundleManifest =
+        serde_json::from_slice(&json).map_err(|e| format!("Invalid bundle.json: {}", e))?;
+    if manifest.format != BUNDLE_FORMAT {
+        return Err("Not a resource bundle".to_string());
+    }
+    if manifest.version > BUNDLE_VERSION {
+        return Err(format!(
+            "Bundle version {} is newer than this app supports",
+            manifest.version
+        ));
+    }
+    Ok(manifest)
+}
+
+/// Extracts a bundled image into `resource_dir`, validating that it decodes.
+/// Returns the file name it was stored under.
+fn extract_image(
+    archive: &mut ZipArchive<fs::File>,
+    entry: &str,
+    resource_dir: &Path,
+) -> Result<String, String> {
+    let bytes = read_entry(archive, entry, MAX_ENTRY_SIZE)?;
+
+    // Only the file name is trusted; the directory part comes from the archive.
+    let filename = Path::new(entry)
+        .file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .ok_or("Invalid entry name")?;
+    let dest = unique_destination(resource_dir, &filename);
+    fs::write(&dest, &bytes).map_err(|e| e.to_string())?;
+
+    if let Err(e) = imaging::validate(&dest) {
+        let _ = fs::remove_file(&dest);
+        return Err(format!("Not a readable image: {}", e));
+    }
+    dest.file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .ok_or_else(|| "Invalid entry name".to_string())
+}
+
+/// A bundled resource with the local ID it will be stored under.
+struct PlannedImport {
+    bundled: Resource,
+    id: String,
+    /// The local resource a `Replace` import overwrites.
+    existing: Option<Resource>,
+    /// Imported as a copy next to a local resource with the same ID.
+    duplicate: bool,
+}
+
+/// A staged resource folder moved into place, with what it takes to undo it.
+struct Installed {
+    staged_dir: PathBuf,
+    resource_dir: PathBuf,
+    /// Whether the staged folder has been moved to `resource_dir`.
+    in_place: bool,
+    /// Trash entry of the replaced resource's folder.
+    replaced: Option<TrashItem>,
+    /// Whether the replaced resource's `.versions` folder was carried over.
+    history_moved: bool,
+}
+
+impl Installed {
+    /// Puts back whatever `install_staged` got done.
+    fn undo(&self, app: &AppHandle) -> Result<(), String> {
+        if self.in_place {
+            move_path(&self.resource_dir, &self.staged_dir)?;
+        }
+        if let Some(item) = &self.replaced {
+            untrash_resource(app, item)?;
+        }
+        if self.history_moved {
+            move_path(
+                &self.staged_dir.join(VERSIONS_DIR),
+                &self.resource_dir.join(VERSIONS_DIR),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Moves a staged resource folder into place, carrying over the version
+/// history of the resource it replaces before that one goes to the trash.
+/// Undoes its own steps if one fails.
+fn install_staged(
+    app: &AppHandle,
+    staged_dir: &Path,
+    resource_dir: &Path,
+    existing: Option<&Resource>,
+) -> Result<Installed, String> {
+    let mut installed = Installed {
+        staged_dir: staged_dir.to_path_buf(),
+        resource_dir: resource_dir.to_path_buf(),
+        in_place: false,
+        replaced: None,
+        history_moved: false,
+    };
+    let result = (|| {
+        if let Some(existing) = existing {
+            let history = resource_dir.join(VERSIONS_DIR);
+            if history.exists() {
+                move_path(&history, &staged_dir.join(VERSIONS_DIR))?;
+                installed.history_moved = true;
+            }
+            // Its version history stays with the resource, not the trash entry
+            installed.replaced = Some(trash_resource(app, existing, Vec::new())?);
+        }
+        move_path(staged_dir, resource_dir)?;
+        installed.in_place = true;
+        Ok::<(), String>(())
+    })();
+    if let Err(e) = result {
+        if let Err(undo) = installed.undo(app) {
+            log::error!(
+                "Failed to undo installing {}: {}",
+                resource_dir.display(),
+                undo
+            );
+        }
+        return Err(e);
+    }
+    Ok(installed)
+}
+
+/// Exports the given resources, their prompt templates and images into a single
+/// `.oneiria` archive. Returns `None` if the user cancels the save dialog.
+#[tauri::command]
+pub async fn export_resource_bundle(
+    app: AppHandle,
+    db: State<'_, Database>,
+    ids: Vec<String>,
+) -> Result<Option<String>, String> {
+    let resources = {
+        let conn = db.lock()?;
+        let mut resources = Vec::new();
+        for id in &ids {
+            resources.push(db::resources::get(&conn, id)?.ok_or("Resource not found")?);
+        }
+        resources
+    };
+    if resources.is_empty() {
+        return Err("No resources selected".to_string());
+    }
+
+    let default_filename = match resources.as_slice() {
+        [single] => format!("{}.{}", single.name, BUNDLE_EXTENSION),
+        _ => format!("resources.{}", BUNDLE_EXTENSION),
+    };
+    let file_path = app
+        .dialog()
+        .file()
+        .set_file_name(&default_filename)
+        .add_filter("Oneiria Bundle", &[BUNDLE_EXTENSION])
+        .blocking_save_file();
+
+    match file_path {
+        Some(FilePath::Path(save_path)) => {
+            write_bundle(&resources, &save_path)?;
+            Ok(Some(save_path.to_string_lossy().to_string()))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Imports a `.oneiria` archive, picked with a dialog unless `path` is given.
+/// Returns `None` if the user cancels the dialog.
+#[tauri::command]
+pub async fn import_resource_bundle(
+    app: AppHandle,
+    db: State<'_, Database>,
+    path: Option<String>,
+    strategy: Option<ConflictStrategy>,
+) -> Result<Option<BundleImportReport>, String> {
+    let strategy = strategy.unwrap_or_default();
+    let bundle_path = match path {
+        Some(path) => path.into(),
+        None => match app
+            .dialog()
+            .file()
+            .add_filter("Oneiria Bundle", &[BUNDLE_EXTENSION])
+            .blocking_pick_file()
+        {
+            Some(FilePath::Path(path)) => path,
+            _ => return Ok(None),
+        },
+    };
+
+    let file = fs::File::open(&bundle_path).map_err(|e| e.to_string())?;
+    let mut archive = ZipArchive::new(file).map_err(|e| e.to_string())?;
+    let manifest = read_manifest(&mut archive)?;
+
+    let mut conn = db.lock()?;
+    let mut report = BundleImportReport {
+        imported: Vec::new(),
+        skipped: Vec::new(),
+        failed_images: Vec::new(),
+        dropped_references: Vec::new(),
+    };
+
+    let base_dir = resources_dir(&app)?;
+
+    // Decide every resource's local ID first so references between bundled
+    // resources can be remapped regardless of order. Bundled IDs are only kept
+    // when they are well-formed UUIDs, since they become folder names.
+    let mut id_map: HashMap<String, String> = HashMap::new();
+    let mut plan: Vec<PlannedImport> = Vec::new();
+    for resource in manifest.resources {
+        let local_id = Uuid::parse_str(&resource.id).ok().map(|id| id.to_string());
+        let existing = match &local_id {
+            Some(id) => db::resources::get(&conn, id)?,
+            None => None,
+        };
+        let (id, existing, duplicate) = match (local_id, existing, strategy) {
+            (Some(id), Some(_), ConflictStrategy::Skip) => {
+                id_map.insert(resource.id.clone(), id);
+                report.skipped.push(resource.name);
+                continue;
+            }
+            (Some(_), Some(_), ConflictStrategy::Duplicate) => {
+                (Uuid::new_v4().to_string(), None, true)
+            }
+            (Some(id), Some(existing), ConflictStrategy::Replace) => (id, Some(existing), false),
+            // A stray folder under the same ID would block moving the import in
+            (Some(id), None, _) if !base_dir.join(&id).exists() => (id, None, false),
+            _ => (Uuid::new_v4().to_string(), None, false),
+        };
+        id_map.insert(resource.id.clone(), id.clone());
+        plan.push(PlannedImport {
+            bundled: resource,
+            id,
+            existing,
+            duplicate,
+        });
+    }
+
+    // Extract everything into a staging folder first and move it into place
+    // inside the database transaction, undoing the moves if anything fails.
+    let staging_dir = base_dir.join(format!(".import-{}", Uuid::new_v4()));
+    let result = stage(
+        &conn,
+        &mut archive,
+        plan,
+        &id_map,
+        &base_dir,
+        &staging_dir,
+        &mut report,
+    )
+    .and_then(|imported| commit_and_install(&app, &mut conn, imported, &base_dir, &staging_dir));
+    if staging_dir.exists() {
+        let _ = fs::remove_dir_all(&staging_dir);
+    }
+    let imported = result?;
+
+    report.imported = imported;
+    Ok(Some(report))
+}
+
+/// Extracts each planned resource into `staging_dir/{id}` and builds its
+/// record, paired with the resource it replaces. Resource references that are
+/// neither in the bundle nor local are dropped and reported.
+fn stage(
+    conn: &rusqlite::Connection,
+    archive: &mut ZipArchive<fs::File>,
+    plan: Vec<PlannedImport>,
+    id_map: &HashMap<String, String>,
+    base_dir: &Path,
+    staging_dir: &Path,
+    report: &mut BundleImportReport,
+) -> Result<Vec<(Resource, Option<Resource>)>, String> {
+    let timestamp = Utc::now().timestamp_millis();
+    let mut imported = Vec::new();
+    for planned in plan {
+        let PlannedImport {
+            bundled,
+            id,
+            existing,
+            duplicate,
+        } = planned;
+        let staged_dir = staging_dir.join(&id);
+        fs::create_dir_all(&staged_dir).map_err(|e| e.to_string())?;
+        // Records point at where the files will live once moved into place
+        let resource_dir: PathBuf = base_dir.join(&id);
+
+        let mut mapping = HashMap::new();
+        let mut images = Vec::new();
+        for entry in &bundled.images {
+            match extract_image(archive, entry, &staged_dir) {
+                Ok(filename) => {
+                    let stored = resource_dir.join(filename).to_string_lossy().to_string();
+                    mapping.insert(entry.clone(), stored.clone());
+                    images.push(stored);
+                }
+                Err(reason) => report.failed_images.push(ImageImportFailure {
+                    path: entry.clone(),
+                    reason,
+                }),
+            }
+        }
+
+        let mut template = bundled
+            .prompt_template
+            .remap_images(&mapping)
+            .remap_resources(id_map);
+        // Images that failed to extract would still point into the archive
+        template.retain_images(|path| images.iter().any(|stored| stored == path));
+        let mut dangling = Vec::new();
+        for reference in template.resource_refs() {
+            let known = id_map.values().any(|id| id == reference)
+                || db::resources::get(conn, reference)?.is_some();
+            if !known && !dangling.iter().any(|d| d == reference) {
+                dangling.push(reference.to_string());
+            }
+        }
+        template.retain_resources(|reference| !dangling.iter().any(|d| d == reference));
+        report.dropped_references.extend(dangling);
+
+        let name = if duplicate {
+            format!("{} (imported)", bundled.name)
+        } else {
+            bundled.name.clone()
+        };
+        imported.push((
+            Resource {
+                id,
+                name,
+                prompt_template: template,
+                images,
+                // Usage on the exporting machine says nothing about this one
+                usage_count: 0,
+                updated_at: timestamp,
+                ..bundled
+            },
+            existing,
+        ));
+    }
+    Ok(imported)
+}
+
+/// Writes all records in one transaction, snapshotting every resource being
+/// replaced, and moves the staged folders into place before it commits. If a
+/// move or the commit fails, the moves are undone and the transaction rolls
+/// back.
+fn commit_and_install(
+    app: &AppHandle,
+    conn: &mut rusqlite::Connection,
+    imported: Vec<(Resource, Option<Resource>)>,
+    base_dir: &Path,
+    staging_dir: &Path,
+) -> Result<Vec<Resource>, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    for (resource, existing) in &imported {
+        match existing {
+            Some(existing) => {
+                versions::snapshot(&tx, &base_dir.join(&existing.id), existing)?;
+                db::resources::update(&tx, resource)?;
+            }
+            None => db::resources::insert(&tx, resource)?,
+        }
+    }
+
+    let mut installed = Vec::new();
+    let mut result = Ok(());
+    for (resource, existing) in &imported {
+        match install_staged(
+            app,
+            &staging_dir.join(&resource.id),
+            &base_dir.join(&resource.id),
+            existing.as_ref(),
+        ) {
+            Ok(done) => installed.push(done),
+            Err(e) => {
+                result = Err(e);
+                break;
+            }
+        }
+    }
+    let result = result.and_then(|_| tx.commit().map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        for done in installed.iter().rev() {
+            if let Err(undo) = done.undo(app) {
+                log::error!(
+                    "Failed to undo installing {}: {}",
+                    done.resource_dir.display(),
+                    undo
+                );
+            }
+        }
+        return Err(e);
+    }
+    Ok(imported.into_iter().map(|(r, _)| r).collect())
+}
@@ -0,0 +1,93 @@
+use crate::commands::gallery::{resolve_output_dir, COMPARISONS_DIR};
+use crate::commands::generate::{
+    configured_providers, generate_with, record_resource_usage, PROVIDERS,
+};
+use crate::models::{GeneratePayload, ProviderComparison, ProviderResult};
+use chrono::{Local, Utc};
+use std::collections::HashSet;
+use std::fs;
+use tauri::AppHandle;
+use uuid::Uuid;
+
+/// Sends the same generation to several providers at once, saving each one's
+/// images under `Comparisons/{timestamp}_{suffix}/{provider}` in the output
+/// directory.
+/// Defaults to every provider with an API key. A failing provider is reported
+/// in its result instead of failing the comparison.
+#[tauri::command]
+pub async fn compare_providers(
+    app: AppHandle,
+    payload: GeneratePayload,
+    providers: Option<Vec<String>>,
+) -> Result<ProviderComparison, String> {
+    let mut providers = match providers {
+        Some(providers) => providers,
+        None => configured_providers(&app)?,
+    };
+    let mut seen = HashSet::new();
+    providers.retain(|p| seen.insert(p.clone()));
+    if providers.is_empty() {
+        return Err("No providers configured. Add an API key in Settings.".to_string());
+    }
+    if let Some(unknown) = providers
+        .iter()
+        .find(|p| !PROVIDERS.iter().any(|(name, _)| name == p))
+    {
+        return Err(format!("Unknown provider: {}", unknown));
+    }
+
+    let folder = resolve_output_dir(&app)?
+        .join(COMPARISONS_DIR)
+        .join(format!(
+            "{}_{}",
+            Local::now().format("%Y%m%d_%H%M%S"),
+            &Uuid::new_v4().simple().to_string()[..8]
+        ));
+    fs::create_dir_all(&folder).map_err(|e| e.to_string())?;
+
+    let handles: Vec<_> = providers
+        .iter()
+        .map(|provider| {
+            let app = app.clone();
+            let payload = payload.clone();
+            let provider = provider.clone();
+            let output_path = folder.join(&provider);
+            tauri::async_runtime::spawn(async move {
+                let started = Utc::now().timestamp_millis();
+                let result = generate_with(&app, payload, &output_path, &provider, false).await;
+                (result, Utc::now().timestamp_millis() - started)
+            })
+        })
+        .collect();
+
+    let mut results = Vec::new();
+    for (provider, handle) in providers.into_iter().zip(handles) {
+        let (result, duration_ms) = handle.await.map_err(|e| e.to_string())?;
+        if let Err(e) = &result {
+            log::warn!("Comparison run on {} failed: {}", provider, e);
+        }
+        let (paths, error) = match result {
+            Ok(paths) => (paths, None),
+            Err(e) => (Vec::new(), Some(e)),
+        };
+        results.push(ProviderResult {
+            provider,
+            paths,
+            error,
+            duration_ms,
+        });
+    }
+
+    // One comparison is one use of its resources, however many providers ran
+    if results.iter().any(|r| r.error.is_none()) {
+        let resource_ids = payload.resource_ids.unwrap_or_default();
+        if let Err(e) = record_resource_usage(&app, &resource_ids) {
+            log::warn!("Failed to update resource usage counts: {}", e);
+        }
+    }
+
+    Ok(ProviderComparison {
+        folder: folder.to_string_lossy().to_string(),
+        results,
+    })
+}
@@ -0,0 +1,102 @@
+use crate::commands::gallery::resolve_output_dir;
+use crate::commands::generate::generation_record;
+use crate::commands::metadata::image_hash;
+use crate::imaging::contact_sheet::{compose, load_caption_font, Layout, Tile};
+use crate::imaging::decode;
+use crate::models::{CaptionField, ContactSheetPayload, GeneratedImage};
+use std::fs;
+use std::path::Path;
+use tauri::AppHandle;
+
+const DEFAULT_CELL_SIZE: u32 = 512;
+
+fn caption_for(
+    app: &AppHandle,
+    path: &str,
+    image: &image::DynamicImage,
+    fields: &[CaptionField],
+) -> Result<Vec<String>, String> {
+    if fields.is_empty() {
+        return Ok(Vec::new());
+    }
+    let record = generation_record(app, &image_hash(app, path)?)?;
+
+    Ok(fields
+        .iter()
+        .map(|field| match field {
+            CaptionField::Filename => Path::new(path)
+                .file_name()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            CaptionField::Prompt => record
+                .as_ref()
+                .map(|r| r.prompt.clone())
+                .unwrap_or_default(),
+            CaptionField::Provider => record
+                .as_ref()
+                .map(|r| format!("{} / {}", r.provider, r.model))
+                .unwrap_or_default(),
+            CaptionField::Size => format!("{}×{}", image.width(), image.height()),
+        })
+        .collect())
+}
+
+/// Composes gallery images into a single grid image saved to the output folder.
+#[tauri::command]
+pub async fn create_contact_sheet(
+    app: AppHandle,
+    payload: ContactSheetPayload,
+) -> Result<GeneratedImage, String> {
+    if payload.paths.is_empty() {
+        return Err("No images selected".to_string());
+    }
+    let fields = payload.captions.unwrap_or_default();
+
+    let font = if fields.is_empty() {
+        None
+    } else {
+        let font = load_caption_font();
+        if font.is_none() {
+            log::warn!("No caption font found, creating contact sheet without captions");
+        }
+        font
+    };
+
+    // Size the sheet up front so an oversized one is rejected before decoding
+    let columns = payload
+        .columns
+        .unwrap_or_else(|| (payload.paths.len() as f64).sqrt().ceil() as u32);
+    let caption_lines = if font.is_some() { fields.len() } else { 0 };
+    let layout = Layout::new(
+        payload.paths.len(),
+        columns,
+        payload.cell_size.unwrap_or(DEFAULT_CELL_SIZE),
+        caption_lines,
+    )?;
+
+    // Only thumbnails are kept, not every full-size image
+    let mut tiles = Vec::new();
+    for path in &payload.paths {
+        let image = decode(Path::new(path))?.image;
+        let caption = caption_for(&app, path, &image, &fields)?;
+        let image = image.thumbnail(layout.cell_size, layout.cell_size);
+        tiles.push(Tile { image, caption });
+    }
+
+    let sheet = compose(&tiles, &layout, font.as_ref());
+
+    let output_path = resolve_output_dir(&app)?;
+    if !output_path.exists() {
+        fs::create_dir_all(&output_path).map_err(|e| e.to_string())?;
+    }
+    let timestamp = chrono::Utc::now().timestamp_millis();
+    let filename = format!("contact_sheet_{}.png", timestamp);
+    let file_path = output_path.join(&filename);
+    sheet.save(&file_path).map_err(|e| e.to_string())?;
+
+    Ok(GeneratedImage {
+        filename,
+        path: file_path.to_string_lossy().to_string(),
+        created_at: timestamp as u64,
+    })
+}
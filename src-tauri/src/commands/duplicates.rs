@@ -0,0 +1,295 @@
+use crate::commands::gallery::scan_gallery;
+use crate::commands::metadata::{hashed_gallery, image_hash, image_hashes};
+use crate::commands::resources::load_resources;
+use crate::imaging::decode;
+use crate::imaging::phash::{average_hash, difference_hash, hamming_distance, perceptual_hash};
+use crate::models::{
+    DuplicateGroup, GeneratedImage, HashAlgorithm, ImageHashes, ImageSource, SimilarImage,
+};
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::Path;
+use tauri::{AppHandle, Wry};
+use tauri_plugin_store::{Store, StoreExt};
+
+const PHASH_STORE: &str = "image_phashes.json";
+const DEFAULT_SIMILARITY_THRESHOLD: u32 = 8;
+const DEFAULT_SIMILAR_LIMIT: usize = 20;
+
+fn compute_hashes(
+    store: &Store<Wry>,
+    content_hash: String,
+    path: &Path,
+) -> Result<ImageHashes, String> {
+    if let Some(hashes) = store
+        .get(&content_hash)
+        .and_then(|v| serde_json::from_value::<ImageHashes>(v).ok())
+        .filter(ImageHashes::is_valid)
+    {
+        return Ok(hashes);
+    }
+
+    let image = decode(path)?.image;
+    let hashes = ImageHashes {
+        content_hash: content_hash.clone(),
+        ahash: format!("{:016x}", average_hash(&image)),
+        dhash: format!("{:016x}", difference_hash(&image)),
+        phash: format!("{:016x}", perceptual_hash(&image)),
+    };
+    store.set(content_hash, json!(hashes));
+    Ok(hashes)
+}
+
+/// Perceptual hashes for images whose content hashes are already known, as
+/// `(path, content hash)` pairs. Files that cannot be decoded are skipped with
+/// a warning.
+fn perceptual_hashes(
+    app: &AppHandle,
+    images: Vec<(String, String)>,
+) -> Result<Vec<(String, ImageHashes)>, String> {
+    let store = app.store(PHASH_STORE).map_err(|e| e.to_string())?;
+
+    let mut hashed = Vec::new();
+    for (path, content_hash) in images {
+        match compute_hashes(&store, content_hash, Path::new(&path)) {
+            Ok(hashes) => hashed.push((path, hashes)),
+            Err(e) => log::warn!("Skipping {}: {}", path, e),
+        }
+    }
+
+    store.save().map_err(|e| e.to_string())?;
+    Ok(hashed)
+}
+
+/// Content and perceptual hashes for each path, cached by content hash.
+/// Files that cannot be read or decoded are skipped with a warning.
+pub(crate) fn hash_images(
+    app: &AppHandle,
+    paths: &[String],
+) -> Result<Vec<(String, ImageHashes)>, String> {
+    let images = paths
+        .iter()
+        .zip(image_hashes(app, paths)?)
+        .filter_map(|(path, hash)| match hash {
+            Ok(hash) => Some((path.clone(), hash)),
+            Err(e) => {
+                log::warn!("Skipping {}: {}", path, e);
+                None
+            }
+        })
+        .collect();
+    perceptual_hashes(app, images)
+}
+
+fn find_root(parents: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while parents[root] != root {
+        root = parents[root];
+    }
+    // Path compression
+    let mut node = i;
+    while parents[node] != root {
+        let next = parents[node];
+        parents[node] = root;
+        node = next;
+    }
+    root
+}
+
+/// Groups hash indices whose values are within `threshold` bits of each other,
+/// transitively. Returns each group of two or more, in index order, with the
+/// largest distance of a link that formed it.
+fn group_similar(values: &[u64], threshold: u32) -> Vec<(Vec<usize>, u32)> {
+    let mut parents: Vec<usize> = (0..values.len()).collect();
+    let mut link_distance = vec![0u32; values.len()];
+    for i in 0..values.len() {
+        for j in (i + 1)..values.len() {
+            let distance = hamming_distance(values[i], values[j]);
+            if distance <= threshold {
+                let (a, b) = (find_root(&mut parents, i), find_root(&mut parents, j));
+                if a != b {
+                    parents[b] = a;
+                    link_distance[a] = link_distance[a].max(link_distance[b]);
+                }
+                link_distance[a] = link_distance[a].max(distance);
+            }
+        }
+    }
+
+    let mut members: HashMap<usize, Vec<usize>> = HashMap::new();
+    for i in 0..values.len() {
+        members
+            .entry(find_root(&mut parents, i))
+            .or_default()
+            .push(i);
+    }
+    let mut groups: Vec<(Vec<usize>, u32)> = members
+        .into_iter()
+        .filter(|(_, indices)| indices.len() > 1)
+        .map(|(root, indices)| (indices, link_distance[root]))
+        .collect();
+    groups.sort();
+    groups
+}
+
+#[tauri::command]
+pub async fn get_image_hashes(app: AppHandle, path: String) -> Result<ImageHashes, String> {
+    let store = app.store(PHASH_STORE).map_err(|e| e.to_string())?;
+    let hashes = compute_hashes(&store, image_hash(&app, &path)?, Path::new(&path))?;
+    store.save().map_err(|e| e.to_string())?;
+    Ok(hashes)
+}
+
+/// Groups gallery images that are byte-for-byte identical.
+#[tauri::command]
+pub async fn find_duplicate_images(app: AppHandle) -> Result<Vec<DuplicateGroup>, String> {
+    let mut by_hash: HashMap<String, Vec<GeneratedImage>> = HashMap::new();
+    for (hash, image) in hashed_gallery(&app)? {
+        by_hash.entry(hash).or_default().push(image);
+    }
+
+    let mut groups: Vec<DuplicateGroup> = by_hash
+        .into_values()
+        .filter(|images| images.len() > 1)
+        .map(|images| DuplicateGroup {
+            images,
+            max_distance: 0,
+        })
+        .collect();
+    groups.sort_by_key(|g| std::cmp::Reverse(g.images.len()));
+    Ok(groups)
+}
+
+/// Groups gallery images whose perceptual hashes are within `threshold` bits
+/// of each other, transitively.
+#[tauri::command]
+pub async fn find_similar_images(
+    app: AppHandle,
+    algorithm: Option<HashAlgorithm>,
+    threshold: Option<u32>,
+) -> Result<Vec<DuplicateGroup>, String> {
+    let algorithm = algorithm.unwrap_or_default();
+    let threshold = threshold.unwrap_or(DEFAULT_SIMILARITY_THRESHOLD);
+
+    let mut gallery: HashMap<String, GeneratedImage> = HashMap::new();
+    let mut content_hashes = Vec::new();
+    for (hash, image) in hashed_gallery(&app)? {
+        content_hashes.push((image.path.clone(), hash));
+        gallery.insert(image.path.clone(), image);
+    }
+    let hashed = perceptual_hashes(&app, content_hashes)?;
+    let values: Vec<u64> = hashed
+        .iter()
+        .map(|(_, hashes)| hashes.perceptual(algorithm))
+        .collect();
+
+    let mut groups: Vec<DuplicateGroup> = group_similar(&values, threshold)
+        .into_iter()
+        .filter_map(|(indices, max_distance)| {
+            let mut images: Vec<GeneratedImage> = indices
+                .into_iter()
+                .filter_map(|i| gallery.get(&hashed[i].0).cloned())
+                .collect();
+            if images.len() < 2 {
+                return None;
+            }
+            images.sort_by_key(|img| std::cmp::Reverse(img.created_at));
+            Some(DuplicateGroup {
+                images,
+                max_distance,
+            })
+        })
+        .collect();
+    groups.sort_by_key(|g| std::cmp::Reverse(g.images.len()));
+    Ok(groups)
+}
+
+fn combined_distance(a: &ImageHashes, b: &ImageHashes) -> u32 {
+    hamming_distance(
+        a.perceptual(HashAlgorithm::Phash),
+        b.perceptual(HashAlgorithm::Phash),
+    ) + hamming_distance(
+        a.perceptual(HashAlgorithm::Dhash),
+        b.perceptual(HashAlgorithm::Dhash),
+    )
+}
+
+/// Ranks gallery and resource images by visual similarity to `path`.
+#[tauri::command]
+pub async fn find_similar(
+    app: AppHandle,
+    path: String,
+    limit: Option<usize>,
+) -> Result<Vec<SimilarImage>, String> {
+    let limit = limit.unwrap_or(DEFAULT_SIMILAR_LIMIT);
+    let (_, target) = hash_images(&app, std::slice::from_ref(&path))?
+        .pop()
+        .ok_or_else(|| format!("Could not read image: {}", path))?;
+
+    let mut sources: HashMap<String, (ImageSource, Option<String>)> = HashMap::new();
+    for image in scan_gallery(&app)? {
+        sources.insert(image.path, (ImageSource::Gallery, None));
+    }
+    for resource in load_resources(&app)? {
+        for image in &resource.images {
+            sources.insert(
+                image.clone(),
+                (ImageSource::Resource, Some(resource.id.clone())),
+            );
+        }
+    }
+    sources.remove(&path);
+
+    let candidates: Vec<String> = sources.keys().cloned().collect();
+    let mut matches: Vec<SimilarImage> = hash_images(&app, &candidates)?
+        .into_iter()
+        .filter_map(|(candidate, hashes)| {
+            let (source, resource_id) = sources.remove(&candidate)?;
+            let distance = combined_distance(&target, &hashes);
+            Some(SimilarImage {
+                filename: Path::new(&candidate)
+                    .file_name()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                path: candidate,
+                source,
+                resource_id,
+                distance,
+                similarity: 1.0 - distance as f64 / 128.0,
+            })
+        })
+        .collect();
+
+    matches.sort_by_key(|m| m.distance);
+    matches.truncate(limit);
+    Ok(matches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn similar_hashes_group_within_the_threshold() {
+        // 0 and 1 differ by 3 bits, 2 is far from both
+        let values = [0b0000, 0b0111, u64::MAX];
+        assert_eq!(group_similar(&values, 3), vec![(vec![0, 1], 3)]);
+        assert!(group_similar(&values, 2).is_empty());
+    }
+
+    #[test]
+    fn groups_are_transitive() {
+        // 0–1 and 1–2 are within 2 bits, 0–2 is 4 bits apart
+        let values = [0b0000, 0b0011, 0b1111, 0xff00];
+        assert_eq!(group_similar(&values, 2), vec![(vec![0, 1, 2], 2)]);
+    }
+
+    #[test]
+    fn separate_clusters_stay_apart() {
+        let values = [0, 1, u64::MAX, u64::MAX - 1, 0xffff_0000];
+        assert_eq!(
+            group_similar(&values, 1),
+            vec![(vec![0, 1], 1), (vec![2, 3], 1)]
+        );
+    }
+}
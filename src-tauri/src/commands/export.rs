@@ -0,0 +1,247 @@
+use crate::commands::albums::album_images;
+use crate::commands::gallery::scan_gallery;
+use crate::commands::generate::generation_record;
+use crate::commands::metadata::{image_hashes, metadata_for_hash, query_images};
+use crate::models::{ExportProgress, ExportSummary, ExportZipPayload, GenerationRecord};
+use serde::Serialize;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_dialog::DialogExt;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// One row of the export manifest.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ManifestEntry {
+    filename: String,
+    original_path: String,
+    hash: String,
+    rating: u8,
+    favorite: bool,
+    tags: Vec<String>,
+    generation: Option<GenerationRecord>,
+}
+
+const CSV_HEADER: [&str; 14] = [
+    "filename",
+    "original_path",
+    "hash",
+    "rating",
+    "favorite",
+    "tags",
+    "prompt",
+    "negative_prompt",
+    "provider",
+    "model",
+    "width",
+    "height",
+    "reference_images",
+    "generated_at",
+];
+
+impl ManifestEntry {
+    fn csv_record(&self) -> Vec<String> {
+        let mut record = vec![
+            self.filename.clone(),
+            self.original_path.clone(),
+            self.hash.clone(),
+            self.rating.to_string(),
+            self.favorite.to_string(),
+            self.tags.join(";"),
+        ];
+        match &self.generation {
+            Some(g) => record.extend([
+                g.prompt.clone(),
+                g.negative_prompt.clone().unwrap_or_default(),
+                g.provider.clone(),
+                g.model.clone(),
+                g.width.to_string(),
+                g.height.to_string(),
+                g.reference_images.join(";"),
+                g.created_at.to_string(),
+            ]),
+            None => record.extend(vec![String::new(); 8]),
+        }
+        record
+    }
+}
+
+fn selected_paths(app: &AppHandle, payload: &ExportZipPayload) -> Result<Vec<String>, String> {
+    if let Some(paths) = &payload.paths {
+        return Ok(paths.clone());
+    }
+    let images = if let Some(album_id) = &payload.album_id {
+        album_images(app, album_id)?
+    } else if let Some(query) = &payload.query {
+        query_images(app, query)?
+    } else {
+        scan_gallery(app)?
+    };
+    Ok(images.into_iter().map(|img| img.path).collect())
+}
+
+/// Archive entry name for `path`, suffixed when the filename is already taken.
+fn unique_entry_name(path: &Path, used: &mut Vec<String>) -> String {
+    let filename = path
+        .file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "image".to_string());
+
+    let mut name = filename.clone();
+    let mut n = 1;
+    while used.contains(&name) {
+        let stem = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        name = match path.extension() {
+            Some(ext) => format!("{}_{}.{}", stem, n, ext.to_string_lossy()),
+            None => format!("{}_{}", stem, n),
+        };
+        n += 1;
+    }
+    used.push(name.clone());
+    name
+}
+
+fn write_manifest_csv(entries: &[ManifestEntry]) -> Result<Vec<u8>, String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(CSV_HEADER).map_err(|e| e.to_string())?;
+    for entry in entries {
+        writer
+            .write_record(entry.csv_record())
+            .map_err(|e| e.to_string())?;
+    }
+    writer.into_inner().map_err(|e| e.to_string())
+}
+
+/// Writes the archive, removing the partial file if anything fails.
+pub(crate) fn write_zip(
+    app: &AppHandle,
+    paths: &[String],
+    archive_path: &Path,
+) -> Result<ExportSummary, String> {
+    let result = write_archive(app, paths, archive_path);
+    if result.is_err() {
+        let _ = fs::remove_file(archive_path);
+    }
+    result
+}
+
+fn write_archive(
+    app: &AppHandle,
+    paths: &[String],
+    archive_path: &Path,
+) -> Result<ExportSummary, String> {
+    let hashes = image_hashes(app, paths)?;
+    let file = fs::File::create(archive_path).map_err(|e| e.to_string())?;
+    let mut zip = ZipWriter::new(file);
+    // Images are already compressed, so store them as-is.
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    let total = paths.len();
+    let mut used_names = Vec::new();
+    let mut entries = Vec::new();
+    let mut skipped = Vec::new();
+
+    for (i, (path_str, hash)) in paths.iter().zip(hashes).enumerate() {
+        let path = PathBuf::from(path_str);
+        let _ = app.emit(
+            "export-progress",
+            ExportProgress {
+                current: i + 1,
+                total,
+                filename: path
+                    .file_name()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+            },
+        );
+
+        let opened = hash.and_then(|hash| {
+            fs::File::open(&path)
+                .map(|f| (f, hash))
+                .map_err(|e| e.to_string())
+        });
+        let (mut source, hash) = match opened {
+            Ok(opened) => opened,
+            Err(e) => {
+                log::warn!("Skipping {}: {}", path_str, e);
+                skipped.push(path_str.clone());
+                continue;
+            }
+        };
+        let metadata = metadata_for_hash(app, &hash)?;
+        let name = unique_entry_name(&path, &mut used_names);
+
+        zip.start_file(name.as_str(), stored)
+            .map_err(|e| e.to_string())?;
+        io::copy(&mut source, &mut zip).map_err(|e| e.to_string())?;
+
+        entries.push(ManifestEntry {
+            filename: name,
+            original_path: path_str.clone(),
+            hash: hash.clone(),
+            rating: metadata.rating,
+            favorite: metadata.favorite,
+            tags: metadata.tags,
+            generation: generation_record(app, &hash)?,
+        });
+    }
+
+    let manifest_json = serde_json::to_vec_pretty(&entries).map_err(|e| e.to_string())?;
+    zip.start_file("manifest.json", deflated)
+        .map_err(|e| e.to_string())?;
+    zip.write_all(&manifest_json).map_err(|e| e.to_string())?;
+
+    let manifest_csv = write_manifest_csv(&entries)?;
+    zip.start_file("manifest.csv", deflated)
+        .map_err(|e| e.to_string())?;
+    zip.write_all(&manifest_csv).map_err(|e| e.to_string())?;
+
+    zip.finish().map_err(|e| e.to_string())?;
+
+    Ok(ExportSummary {
+        archive_path: archive_path.to_string_lossy().to_string(),
+        exported: entries.len(),
+        skipped,
+    })
+}
+
+/// Exports gallery images and a manifest of their generation parameters to a ZIP.
+/// Returns `None` if the user cancels the save dialog.
+#[tauri::command]
+pub async fn export_images_zip(
+    app: AppHandle,
+    payload: ExportZipPayload,
+) -> Result<Option<ExportSummary>, String> {
+    use tauri_plugin_dialog::FilePath;
+
+    let paths = selected_paths(&app, &payload)?;
+    if paths.is_empty() {
+        return Err("No images to export".to_string());
+    }
+
+    let default_filename = payload.default_filename.unwrap_or_else(|| {
+        format!(
+            "oneiria_export_{}.zip",
+            chrono::Local::now().format("%Y%m%d_%H%M%S")
+        )
+    });
+
+    let file_path = app
+        .dialog()
+        .file()
+        .set_file_name(&default_filename)
+        .add_filter("ZIP Archive", &["zip"])
+        .blocking_save_file();
+
+    match file_path {
+        Some(FilePath::Path(save_path)) => write_zip(&app, &paths, &save_path).map(Some),
+        _ => Ok(None),
+    }
+}
@@ -1,42 +1,59 @@
-use crate::models::GeneratedImage;
+use crate::commands::trash::trash_image;
+use crate::imaging::{export_image, validate_export_options};
+use crate::models::{GeneratedImage, ImageExportOptions, ImageFormat, TrashItem};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::UNIX_EPOCH;
 use tauri::{AppHandle, Manager};
 use tauri_plugin_dialog::DialogExt;
 use tauri_plugin_opener::OpenerExt;
 use tauri_plugin_store::StoreExt;
 
-#[tauri::command]
-pub async fn list_gallery_images(app: AppHandle) -> Result<Vec<GeneratedImage>, String> {
+const GALLERY_EXTENSIONS: [&str; 4] = ["png", "jpg", "jpeg", "webp"];
+
+/// Resolves the configured output directory, relative paths being rooted in Documents.
+pub(crate) fn resolve_output_dir(app: &AppHandle) -> Result<PathBuf, String> {
     let store = app.store("settings.json").map_err(|e| e.to_string())?;
     let output_dir_str = store
         .get("output_dir")
         .and_then(|v| v.as_str().map(|s| s.to_string()))
         .unwrap_or("Oneiria/Outputs".to_string());
 
-    let output_path = if output_dir_str.starts_with('/') {
-        PathBuf::from(output_dir_str)
+    if output_dir_str.starts_with('/') {
+        Ok(PathBuf::from(output_dir_str))
     } else {
-        app.path()
+        Ok(app
+            .path()
             .document_dir()
             .map_err(|e| e.to_string())?
-            .join(output_dir_str)
-    };
-
-    if !output_path.exists() {
-        return Ok(Vec::new());
+            .join(output_dir_str))
     }
+}
 
-    let mut images = Vec::new();
-    let entries = fs::read_dir(&output_path).map_err(|e| e.to_string())?;
+/// Subfolders of the output directory that batch runs and provider comparisons
+/// save into, one folder per run.
+pub(crate) const BATCHES_DIR: &str = "Batches";
+pub(crate) const COMPARISONS_DIR: &str = "Comparisons";
+
+/// Adds the gallery images in `dir` to `images`, descending into subfolders
+/// when `recursive` is set.
+fn collect_images(
+    dir: &Path,
+    recursive: bool,
+    images: &mut Vec<GeneratedImage>,
+) -> Result<(), String> {
+    let entries = fs::read_dir(dir).map_err(|e| e.to_string())?;
 
     for entry in entries {
         let entry = entry.map_err(|e| e.to_string())?;
         let path = entry.path();
-        if path.is_file() {
+        if path.is_dir() {
+            if recursive {
+                collect_images(&path, true, images)?;
+            }
+        } else if path.is_file() {
             if let Some(ext) = path.extension().and_then(|s| s.to_str()) {
-                if ["png", "jpg", "jpeg", "webp"].contains(&ext.to_lowercase().as_str()) {
+                if GALLERY_EXTENSIONS.contains(&ext.to_lowercase().as_str()) {
                     let metadata = fs::metadata(&path).map_err(|e| e.to_string())?;
                     let created = metadata
                         .created()
@@ -54,13 +71,59 @@ pub async fn list_gallery_images(app: AppHandle) -> Result<Vec<GeneratedImage>,
             }
         }
     }
+    Ok(())
+}
+
+/// Scans the output directory for images, newest first, including batch and
+/// comparison runs.
+pub(crate) fn scan_gallery(app: &AppHandle) -> Result<Vec<GeneratedImage>, String> {
+    let output_path = resolve_output_dir(app)?;
+
+    if !output_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut images = Vec::new();
+    collect_images(&output_path, false, &mut images)?;
+    for subdir in [BATCHES_DIR, COMPARISONS_DIR] {
+        let path = output_path.join(subdir);
+        if path.is_dir() {
+            collect_images(&path, true, &mut images)?;
+        }
+    }
 
     // Sort by newest first
-    images.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    images.sort_by_key(|img| std::cmp::Reverse(img.created_at));
 
     Ok(images)
 }
 
+#[tauri::command]
+pub async fn list_gallery_images(app: AppHandle) -> Result<Vec<GeneratedImage>, String> {
+    scan_gallery(&app)
+}
+
+/// Resolves `path` and checks it lies inside the output directory, so commands
+/// acting on gallery images cannot reach other files.
+fn gallery_image_path(app: &AppHandle, path: &str) -> Result<PathBuf, String> {
+    let path = Path::new(path)
+        .canonicalize()
+        .map_err(|_| format!("Image not found: {}", path))?;
+    let output_dir = resolve_output_dir(app)?
+        .canonicalize()
+        .map_err(|e| e.to_string())?;
+    if !path.starts_with(&output_dir) {
+        return Err(format!("Not a gallery image: {}", path.display()));
+    }
+    Ok(path)
+}
+
+/// Moves a gallery image to the in-app trash.
+#[tauri::command]
+pub async fn delete_image(app: AppHandle, path: String) -> Result<TrashItem, String> {
+    trash_image(&app, &gallery_image_path(&app, &path)?)
+}
+
 #[tauri::command]
 pub async fn open_image_in_viewer(app: AppHandle, path: String) -> Result<(), String> {
     app.opener()
@@ -74,21 +137,36 @@ pub async fn download_image(
     app: AppHandle,
     source_path: String,
     default_filename: String,
+    options: Option<ImageExportOptions>,
 ) -> Result<bool, String> {
     use tauri_plugin_dialog::FilePath;
 
+    let options = options.unwrap_or_default();
+    validate_export_options(&options)?;
+    let target_format = options.format.or_else(|| {
+        Path::new(&source_path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .and_then(ImageFormat::from_extension)
+    });
+
+    let mut dialog = app.dialog().file();
+    dialog = match target_format {
+        Some(format) => {
+            let filename = Path::new(&default_filename).with_extension(format.extension());
+            dialog
+                .set_file_name(filename.to_string_lossy())
+                .add_filter(format.extension().to_uppercase(), &[format.extension()])
+        }
+        None => dialog
+            .set_file_name(&default_filename)
+            .add_filter("Images", &["png", "jpg", "jpeg", "webp", "avif"]),
+    };
+
     // Use blocking dialog (save dialog returns Option<FilePath>)
-    let file_path = app
-        .dialog()
-        .file()
-        .set_file_name(&default_filename)
-        .add_filter("Images", &["png", "jpg", "jpeg", "webp"])
-        .blocking_save_file();
-
-    match file_path {
+    match dialog.blocking_save_file() {
         Some(FilePath::Path(save_path)) => {
-            // Copy the file using std::fs (no permission restrictions)
-            fs::copy(&source_path, &save_path).map_err(|e| e.to_string())?;
+            export_image(Path::new(&source_path), &save_path, &options)?;
             Ok(true)
         }
         _ => {
@@ -1,19 +1,41 @@
-use crate::models::GeneratePayload;
+use crate::commands::gallery::resolve_output_dir;
+use crate::commands::metadata::image_hash;
+use crate::db::{self, Database};
+use crate::models::{GeneratePayload, GenerationRecord};
+use crate::prompt::variables;
 use base64::prelude::*;
 use reqwest::Client;
 use serde_json::json;
 use std::fs;
 use std::io::Write;
-use std::path::{Path, PathBuf};
+use std::path::Path;
 use tauri::{AppHandle, Manager};
 use tauri_plugin_store::StoreExt;
 
+const GENERATIONS_STORE: &str = "generations.json";
+// Doubao recommended model from docs
+const DOUBAO_MODEL: &str = "doubao-seedream-4-5-251128";
+const ZHIPU_MODEL: &str = "glm-image";
+/// Supported providers and the settings key holding each one's API key.
+pub(crate) const PROVIDERS: &[(&str, &str)] =
+    &[("doubao", "doubao_api_key"), ("zhipu", "zhipu_api_key")];
+
 #[tauri::command]
 pub async fn generate_image(
     app: AppHandle,
     payload: GeneratePayload,
 ) -> Result<Vec<String>, String> {
-    // 1. Get Settings
+    let output_path = resolve_output_dir(&app)?;
+    generate_into(&app, payload, &output_path).await
+}
+
+/// Runs a generation with the configured provider, saving the images in
+/// `output_path` and recording their parameters.
+pub(crate) async fn generate_into(
+    app: &AppHandle,
+    payload: GeneratePayload,
+    output_path: &Path,
+) -> Result<Vec<String>, String> {
     let store = app.store("settings.json").map_err(|e| e.to_string())?;
 
     let provider = store
@@ -21,31 +43,112 @@ pub async fn generate_image(
         .and_then(|v| v.as_str().map(|s| s.to_string()))
         .unwrap_or_else(|| "doubao".to_string());
 
-    let output_dir_str = store
-        .get("output_dir")
-        .and_then(|v| v.as_str().map(|s| s.to_string()))
-        .unwrap_or("Oneiria/Outputs".to_string());
+    generate_with(app, payload, output_path, &provider, true).await
+}
 
-    // 2. Resolve Output Path
-    let output_path = if output_dir_str.starts_with('/') {
-        PathBuf::from(output_dir_str)
-    } else {
-        app.path()
-            .document_dir()
-            .map_err(|e| e.to_string())?
-            .join(output_dir_str)
-    };
+/// Providers with an API key in settings.
+pub(crate) fn configured_providers(app: &AppHandle) -> Result<Vec<String>, String> {
+    let store = app.store("settings.json").map_err(|e| e.to_string())?;
+    Ok(PROVIDERS
+        .iter()
+        .filter(|(_, key)| {
+            store
+                .get(*key)
+                .and_then(|v| v.as_str().map(|s| !s.trim().is_empty()))
+                .unwrap_or(false)
+        })
+        .map(|(name, _)| name.to_string())
+        .collect())
+}
+
+/// Like `generate_into`, but with `provider` instead of the configured one.
+/// Callers running one generation on several providers pass `record_usage:
+/// false` and call `record_resource_usage` once themselves.
+pub(crate) async fn generate_with(
+    app: &AppHandle,
+    mut payload: GeneratePayload,
+    output_path: &Path,
+    provider: &str,
+    record_usage: bool,
+) -> Result<Vec<String>, String> {
+    payload.prompt = variables::substitute(
+        &payload.prompt,
+        &payload.variables.clone().unwrap_or_default(),
+    );
+
+    let store = app.store("settings.json").map_err(|e| e.to_string())?;
 
     if !output_path.exists() {
-        fs::create_dir_all(&output_path).map_err(|e| e.to_string())?;
+        fs::create_dir_all(output_path).map_err(|e| e.to_string())?;
     }
 
     let client = Client::new();
 
-    match provider.as_str() {
-        "zhipu" => generate_zhipu(&store, &client, &output_path, payload).await,
-        _ => generate_doubao(&store, &client, &output_path, payload).await,
+    let (provider, model) = match provider {
+        "zhipu" => ("zhipu", ZHIPU_MODEL),
+        _ => ("doubao", DOUBAO_MODEL),
+    };
+    let record = GenerationRecord {
+        prompt: payload.prompt.clone(),
+        negative_prompt: payload.negative_prompt.clone(),
+        provider: provider.to_string(),
+        model: model.to_string(),
+        width: payload.width,
+        height: payload.height,
+        reference_images: payload.reference_images.clone().unwrap_or_default(),
+        resource_ids: payload.resource_ids.clone().unwrap_or_default(),
+        created_at: chrono::Utc::now().timestamp_millis(),
+    };
+
+    let saved_paths = match provider {
+        "zhipu" => generate_zhipu(&store, &client, output_path, payload).await?,
+        _ => generate_doubao(&store, &client, output_path, payload).await?,
+    };
+
+    // The images are already on disk; a failed record should not fail the generation.
+    if let Err(e) = record_generation(app, &saved_paths, &record) {
+        log::warn!("Failed to record generation parameters: {}", e);
+    }
+    if record_usage {
+        if let Err(e) = record_resource_usage(app, &record.resource_ids) {
+            log::warn!("Failed to update resource usage counts: {}", e);
+        }
+    }
+
+    Ok(saved_paths)
+}
+
+fn record_generation(
+    app: &AppHandle,
+    paths: &[String],
+    record: &GenerationRecord,
+) -> Result<(), String> {
+    let store = app.store(GENERATIONS_STORE).map_err(|e| e.to_string())?;
+    for path in paths {
+        let hash = image_hash(app, path)?;
+        store.set(hash, json!(record));
     }
+    store.save().map_err(|e| e.to_string())
+}
+
+pub(crate) fn record_resource_usage(
+    app: &AppHandle,
+    resource_ids: &[String],
+) -> Result<(), String> {
+    let mut ids = resource_ids.to_vec();
+    ids.sort();
+    ids.dedup();
+    let conn = app.state::<Database>().inner().lock()?;
+    db::resources::increment_usage(&conn, &ids)
+}
+
+/// Generation parameters stored for an output image, if it was made by this app.
+pub(crate) fn generation_record(
+    app: &AppHandle,
+    hash: &str,
+) -> Result<Option<GenerationRecord>, String> {
+    let store = app.store(GENERATIONS_STORE).map_err(|e| e.to_string())?;
+    Ok(store.get(hash).and_then(|v| serde_json::from_value(v).ok()))
 }
 
 async fn generate_doubao(
@@ -60,15 +163,13 @@ async fn generate_doubao(
         .ok_or("Doubao API Token not found. Please configure it in Settings.")?;
 
     let url = "https://ark.cn-beijing.volces.com/api/v3/images/generations";
-    // Doubao recommended model from docs
-    let model = "doubao-seedream-4-5-251128";
 
     // Map size or use 2K if not strictly defined.
     // Using explicit resolution if possible, otherwise default to user's choice or 2K.
     let size_str = format!("{}x{}", payload.width, payload.height);
 
     let mut body = json!({
-        "model": model,
+        "model": DOUBAO_MODEL,
         "prompt": payload.prompt,
         "sequential_image_generation": if payload.count > 1 { "auto" } else { "disabled" },
         "response_format": "url",
@@ -157,7 +258,6 @@ async fn generate_zhipu(
         .ok_or("Zhipu API Token not found. Please configure it in Settings.")?;
 
     let url = "https://open.bigmodel.cn/api/paas/v4/images/generations";
-    let model = "glm-image";
 
     let size_str = format!("{}x{}", payload.width, payload.height);
 
@@ -169,7 +269,7 @@ async fn generate_zhipu(
 
     // Zhipu only supports text-to-image, no reference images
     let body = json!({
-        "model": model,
+        "model": ZHIPU_MODEL,
         "prompt": payload.prompt,
         "size": size_str,
         "watermark_enabled": watermark_enabled
@@ -0,0 +1,238 @@
+use crate::commands::resources::resources_dir;
+use crate::commands::trash::{move_path, trash_folder};
+use crate::commands::versions::snapshot;
+use crate::db::{self, Database};
+use crate::imaging;
+use crate::models::{
+    IntegrityIssue, IntegrityIssueKind, IntegrityReport, OrphanFolderAction, PromptTemplate,
+    Resource,
+};
+use chrono::Utc;
+use rusqlite::Connection;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, State};
+use uuid::Uuid;
+
+/// Why an image file is unusable, if it is.
+fn image_problem(path: &str) -> Option<(IntegrityIssueKind, Option<String>)> {
+    if !Path::new(path).is_file() {
+        return Some((IntegrityIssueKind::MissingImage, None));
+    }
+    imaging::validate(Path::new(path))
+        .err()
+        .map(|e| (IntegrityIssueKind::UnreadableImage, Some(e.to_string())))
+}
+
+fn check_resource(resource: &Resource) -> Vec<IntegrityIssue> {
+    let issue = |kind, path: &str, detail| IntegrityIssue {
+        kind,
+        resource_id: Some(resource.id.clone()),
+        resource_name: Some(resource.name.clone()),
+        path: path.to_string(),
+        detail,
+    };
+
+    let mut issues = Vec::new();
+    for image in &resource.images {
+        if let Some((kind, detail)) = image_problem(image) {
+            issues.push(issue(kind, image, detail));
+        }
+    }
+    for reference in resource.prompt_template.image_refs() {
+        if !resource.images.iter().any(|img| img == reference) || !Path::new(reference).is_file() {
+            issues.push(issue(
+                IntegrityIssueKind::DanglingTemplateReference,
+                reference,
+                None,
+            ));
+        }
+    }
+    issues
+}
+
+/// Folders under the resources directory that no resource owns. Dot-prefixed
+/// folders (such as an interrupted bundle import) are not resources.
+fn orphan_folders(base_dir: &Path, resources: &[Resource]) -> Vec<PathBuf> {
+    let ids: HashSet<&str> = resources.iter().map(|r| r.id.as_str()).collect();
+    let Ok(entries) = fs::read_dir(base_dir) else {
+        return Vec::new();
+    };
+    let mut folders: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .filter(|p| {
+            p.file_name().is_some_and(|name| {
+                let name = name.to_string_lossy();
+                !name.starts_with('.') && !ids.contains(name.as_ref())
+            })
+        })
+        .collect();
+    folders.sort();
+    folders
+}
+
+/// Moves an orphan folder to `base_dir/{id}` under a fresh ID, unless its name
+/// already is an unused resource ID, and builds a record for it named after
+/// the folder and holding its readable images.
+fn adopt_folder(base_dir: &Path, folder: &Path) -> Result<(PathBuf, Resource), String> {
+    let name = folder
+        .file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let id = match Uuid::parse_str(&name) {
+        Ok(id) if id.to_string() == name => name.clone(),
+        _ => Uuid::new_v4().to_string(),
+    };
+    let resource_dir = base_dir.join(&id);
+    if resource_dir != folder {
+        move_path(folder, &resource_dir)?;
+    }
+
+    let mut images: Vec<String> = fs::read_dir(&resource_dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.is_file() && imaging::validate(p).is_ok())
+                .map(|p| p.to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+    images.sort();
+
+    let timestamp = Utc::now().timestamp_millis();
+    let resource = Resource {
+        id,
+        name,
+        description: None,
+        prompt_template: PromptTemplate::default(),
+        images,
+        category: None,
+        tags: Vec::new(),
+        usage_count: 0,
+        created_at: timestamp,
+        updated_at: timestamp,
+    };
+    Ok((resource_dir, resource))
+}
+
+/// Adopts every orphan folder in one transaction, moving the renamed folders
+/// back if it fails.
+fn adopt_folders(
+    conn: &mut Connection,
+    base_dir: &Path,
+    orphans: &[PathBuf],
+) -> Result<(), String> {
+    let mut moved: Vec<(&Path, PathBuf)> = Vec::new();
+    let result = (|| {
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        for folder in orphans {
+            let (resource_dir, resource) = adopt_folder(base_dir, folder)?;
+            if resource_dir != *folder {
+                moved.push((folder, resource_dir));
+            }
+            db::resources::insert(&tx, &resource)?;
+        }
+        tx.commit().map_err(|e| e.to_string())
+    })();
+    if result.is_err() {
+        for (folder, resource_dir) in moved.iter().rev() {
+            if let Err(e) = move_path(resource_dir, folder) {
+                log::error!("Failed to move {} back: {}", resource_dir.display(), e);
+            }
+        }
+    }
+    result
+}
+
+fn scan(base_dir: &Path, resources: &[Resource]) -> Vec<IntegrityIssue> {
+    let mut issues: Vec<IntegrityIssue> = resources.iter().flat_map(check_resource).collect();
+    issues.extend(
+        orphan_folders(base_dir, resources)
+            .into_iter()
+            .map(|folder| IntegrityIssue {
+                kind: IntegrityIssueKind::OrphanFolder,
+                resource_id: None,
+                resource_name: None,
+                path: folder.to_string_lossy().to_string(),
+                detail: None,
+            }),
+    );
+    issues
+}
+
+/// Drops unusable images and dangling template references from every affected
+/// resource, snapshotting each one first so the repair can be undone.
+fn repair_resources(
+    conn: &mut Connection,
+    base_dir: &Path,
+    resources: Vec<Resource>,
+) -> Result<(), String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    for mut resource in resources {
+        if check_resource(&resource).is_empty() {
+            continue;
+        }
+        snapshot(&tx, &base_dir.join(&resource.id), &resource)?;
+
+        resource.images.retain(|img| image_problem(img).is_none());
+        let images = resource.images.clone();
+        resource
+            .prompt_template
+            .retain_images(|path| images.iter().any(|img| img == path));
+        resource.updated_at = Utc::now().timestamp_millis().max(resource.updated_at + 1);
+        db::resources::update(&tx, &resource)?;
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Reports problems in the resource library without changing anything.
+#[tauri::command]
+pub async fn check_resource_integrity(
+    app: AppHandle,
+    db: State<'_, Database>,
+) -> Result<IntegrityReport, String> {
+    let resources = {
+        let conn = db.lock()?;
+        db::resources::list(&conn)?
+    };
+    Ok(IntegrityReport {
+        issues: scan(&resources_dir(&app)?, &resources),
+        repaired: false,
+    })
+}
+
+/// Fixes the problems `check_resource_integrity` reports and returns them.
+/// Orphan folders are handled according to `orphan_folders` (kept by default).
+#[tauri::command]
+pub async fn repair_resource_integrity(
+    app: AppHandle,
+    db: State<'_, Database>,
+    orphan_folders: Option<OrphanFolderAction>,
+) -> Result<IntegrityReport, String> {
+    let base_dir = resources_dir(&app)?;
+    let mut conn = db.lock()?;
+    let resources = db::resources::list(&conn)?;
+    let issues = scan(&base_dir, &resources);
+
+    let orphans = self::orphan_folders(&base_dir, &resources);
+    repair_resources(&mut conn, &base_dir, resources)?;
+
+    match orphan_folders.unwrap_or(OrphanFolderAction::Keep) {
+        OrphanFolderAction::Keep => {}
+        OrphanFolderAction::Adopt => adopt_folders(&mut conn, &base_dir, &orphans)?,
+        OrphanFolderAction::Delete => {
+            for folder in &orphans {
+                trash_folder(&app, folder)?;
+            }
+        }
+    }
+
+    Ok(IntegrityReport {
+        issues,
+        repaired: true,
+    })
+}
@@ -0,0 +1,264 @@
+use crate::commands::gallery::scan_gallery;
+use crate::models::{GeneratedImage, ImageMetadata, ImageQuery};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+use tauri::{AppHandle, Wry};
+use tauri_plugin_store::{Store, StoreExt};
+
+const METADATA_STORE: &str = "image_metadata.json";
+const HASH_CACHE_STORE: &str = "image_hashes.json";
+
+/// Cached content hash of a file, invalidated when its size or mtime changes.
+#[derive(Serialize, Deserialize)]
+struct HashCacheEntry {
+    hash: String,
+    len: u64,
+    modified: u64,
+}
+
+/// SHA-256 of the file contents, hex encoded.
+pub(crate) fn content_hash(path: &Path) -> Result<String, String> {
+    let mut file = fs::File::open(path).map_err(|e| e.to_string())?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).map_err(|e| e.to_string())?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Looks up the content hash in the path cache, rehashing the file if it changed.
+/// The caller is responsible for saving the cache store.
+pub(crate) fn cached_content_hash(cache: &Store<Wry>, path: &Path) -> Result<String, String> {
+    let metadata = fs::metadata(path).map_err(|e| e.to_string())?;
+    let len = metadata.len();
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default();
+
+    let key = path.to_string_lossy().to_string();
+    if let Some(entry) = cache
+        .get(&key)
+        .and_then(|v| serde_json::from_value::<HashCacheEntry>(v).ok())
+    {
+        if entry.len == len && entry.modified == modified {
+            return Ok(entry.hash);
+        }
+    }
+
+    let hash = content_hash(path)?;
+    cache.set(
+        key,
+        json!(HashCacheEntry {
+            hash: hash.clone(),
+            len,
+            modified,
+        }),
+    );
+    Ok(hash)
+}
+
+fn load_metadata(store: &Store<Wry>, hash: &str) -> ImageMetadata {
+    store
+        .get(hash)
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_else(|| ImageMetadata {
+            hash: hash.to_string(),
+            ..Default::default()
+        })
+}
+
+fn update_metadata<F>(app: &AppHandle, path: &str, apply: F) -> Result<ImageMetadata, String>
+where
+    F: FnOnce(&mut ImageMetadata) -> Result<(), String>,
+{
+    let hash = image_hash(app, path)?;
+
+    let store = app.store(METADATA_STORE).map_err(|e| e.to_string())?;
+    let mut metadata = load_metadata(&store, &hash);
+    apply(&mut metadata)?;
+    metadata.updated_at = Utc::now().timestamp_millis();
+
+    store.set(hash, json!(metadata));
+    store.save().map_err(|e| e.to_string())?;
+
+    Ok(metadata)
+}
+
+pub(crate) fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim().to_string();
+        if !tag.is_empty() && !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    normalized
+}
+
+#[tauri::command]
+pub async fn get_image_metadata(app: AppHandle, path: String) -> Result<ImageMetadata, String> {
+    let hash = image_hash(&app, &path)?;
+
+    let store = app.store(METADATA_STORE).map_err(|e| e.to_string())?;
+    Ok(load_metadata(&store, &hash))
+}
+
+#[tauri::command]
+pub async fn set_image_rating(
+    app: AppHandle,
+    path: String,
+    rating: u8,
+) -> Result<ImageMetadata, String> {
+    if rating > 5 {
+        return Err(format!("Rating must be between 0 and 5, got {}", rating));
+    }
+    update_metadata(&app, &path, |m| {
+        m.rating = rating;
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub async fn toggle_image_favorite(app: AppHandle, path: String) -> Result<ImageMetadata, String> {
+    update_metadata(&app, &path, |m| {
+        m.favorite = !m.favorite;
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub async fn add_image_tags(
+    app: AppHandle,
+    path: String,
+    tags: Vec<String>,
+) -> Result<ImageMetadata, String> {
+    update_metadata(&app, &path, |m| {
+        for tag in normalize_tags(tags) {
+            if !m.tags.contains(&tag) {
+                m.tags.push(tag);
+            }
+        }
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub async fn remove_image_tags(
+    app: AppHandle,
+    path: String,
+    tags: Vec<String>,
+) -> Result<ImageMetadata, String> {
+    let tags = normalize_tags(tags);
+    update_metadata(&app, &path, |m| {
+        m.tags.retain(|t| !tags.contains(t));
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub async fn list_image_tags(app: AppHandle) -> Result<Vec<String>, String> {
+    let store = app.store(METADATA_STORE).map_err(|e| e.to_string())?;
+    let mut tags: Vec<String> = Vec::new();
+    for (_, value) in store.entries() {
+        if let Ok(metadata) = serde_json::from_value::<ImageMetadata>(value) {
+            for tag in metadata.tags {
+                if !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+            }
+        }
+    }
+    tags.sort();
+    Ok(tags)
+}
+
+/// Scans the gallery and pairs each image with its content hash.
+/// Images that cannot be read are skipped with a warning.
+pub(crate) fn hashed_gallery(app: &AppHandle) -> Result<Vec<(String, GeneratedImage)>, String> {
+    let cache = app.store(HASH_CACHE_STORE).map_err(|e| e.to_string())?;
+
+    let mut hashed = Vec::new();
+    for image in scan_gallery(app)? {
+        match cached_content_hash(&cache, Path::new(&image.path)) {
+            Ok(hash) => hashed.push((hash, image)),
+            Err(e) => log::warn!("Skipping {}: {}", image.path, e),
+        }
+    }
+
+    cache.save().map_err(|e| e.to_string())?;
+    Ok(hashed)
+}
+
+/// Content hashes for several images, in the order given, going through the
+/// path cache and saving it once.
+pub(crate) fn image_hashes(
+    app: &AppHandle,
+    paths: &[String],
+) -> Result<Vec<Result<String, String>>, String> {
+    let cache = app.store(HASH_CACHE_STORE).map_err(|e| e.to_string())?;
+    let hashes = paths
+        .iter()
+        .map(|path| cached_content_hash(&cache, Path::new(path)))
+        .collect();
+    cache.save().map_err(|e| e.to_string())?;
+    Ok(hashes)
+}
+
+/// Content hash of a single image, going through the path cache.
+pub(crate) fn image_hash(app: &AppHandle, path: &str) -> Result<String, String> {
+    let cache = app.store(HASH_CACHE_STORE).map_err(|e| e.to_string())?;
+    let hash = cached_content_hash(&cache, Path::new(path))?;
+    cache.save().map_err(|e| e.to_string())?;
+    Ok(hash)
+}
+
+/// Metadata for a content hash, defaulting to an unrated, untagged entry.
+pub(crate) fn metadata_for_hash(app: &AppHandle, hash: &str) -> Result<ImageMetadata, String> {
+    let store = app.store(METADATA_STORE).map_err(|e| e.to_string())?;
+    Ok(load_metadata(&store, hash))
+}
+
+pub(crate) fn query_images(
+    app: &AppHandle,
+    query: &ImageQuery,
+) -> Result<Vec<GeneratedImage>, String> {
+    let store = app.store(METADATA_STORE).map_err(|e| e.to_string())?;
+    let wanted_tags = normalize_tags(query.tags.clone().unwrap_or_default());
+
+    let mut results = Vec::new();
+    for (hash, image) in hashed_gallery(app)? {
+        let metadata = load_metadata(&store, &hash);
+
+        if let Some(favorite) = query.favorite {
+            if metadata.favorite != favorite {
+                continue;
+            }
+        }
+        if let Some(min_rating) = query.min_rating {
+            if metadata.rating < min_rating {
+                continue;
+            }
+        }
+        if !wanted_tags.iter().all(|t| metadata.tags.contains(t)) {
+            continue;
+        }
+
+        results.push(image);
+    }
+
+    Ok(results)
+}
+
+#[tauri::command]
+pub async fn query_gallery_images(
+    app: AppHandle,
+    query: ImageQuery,
+) -> Result<Vec<GeneratedImage>, String> {
+    query_images(&app, &query)
+}
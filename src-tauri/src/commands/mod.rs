@@ -1,4 +1,18 @@
+pub mod albums;
+pub mod batch;
+pub mod bundles;
+pub mod compare;
+pub mod contact_sheet;
+pub mod duplicates;
+pub mod export;
 pub mod gallery;
 pub mod generate;
+pub mod integrity;
+pub mod metadata;
+pub mod prompt;
 pub mod resources;
+pub mod search;
 pub mod settings;
+pub mod trash;
+pub mod versions;
+pub mod wildcards;
@@ -0,0 +1,15 @@
+use crate::db::Database;
+use crate::models::{RenderPromptPayload, RenderedPrompt};
+use crate::prompt;
+use tauri::State;
+
+/// Renders studio prompt content into the prompt text and reference images to
+/// send for generation.
+#[tauri::command]
+pub async fn render_prompt(
+    db: State<'_, Database>,
+    payload: RenderPromptPayload,
+) -> Result<RenderedPrompt, String> {
+    let conn = db.lock()?;
+    prompt::render::render(&conn, &payload.content, &payload.images)
+}
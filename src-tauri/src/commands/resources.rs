@@ -1,131 +1,491 @@
-use crate::models::{CreateResourcePayload, Resource, UpdateResourcePayload};
+use crate::commands::generate::generation_record;
+use crate::commands::metadata::{image_hash, normalize_tags};
+use crate::commands::trash::{trash_resource, untrash_resource};
+use crate::commands::versions::snapshot;
+use crate::db::{self, Database};
+use crate::imaging;
+use crate::models::{
+    CreateResourcePayload, ImageImportFailure, PromptContent, PromptTemplate, Resource,
+    ResourceImportResult, UpdateResourcePayload,
+};
+use crate::prompt;
 use chrono::Utc;
+use rusqlite::Connection;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
-use tauri::{AppHandle, Manager};
-use tauri_plugin_store::StoreExt;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, State};
 use uuid::Uuid;
 
+pub(crate) fn resources_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let doc_dir = app.path().document_dir().map_err(|e| e.to_string())?;
+    Ok(doc_dir.join("Oneiria/Resources"))
+}
+
+pub(crate) fn load_resources(app: &AppHandle) -> Result<Vec<Resource>, String> {
+    let conn = app.state::<Database>().inner().lock()?;
+    db::resources::list(&conn)
+}
+
+/// Destination for `filename` in `dir`, numbered if the name is already taken.
+pub(crate) fn unique_destination(dir: &Path, filename: &str) -> PathBuf {
+    let candidate = dir.join(filename);
+    if !candidate.exists() {
+        return candidate;
+    }
+    let path = Path::new(filename);
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let ext = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+
+    let mut n = 1;
+    loop {
+        let candidate = dir.join(format!("{}_{}{}", stem, n, ext));
+        if !candidate.exists() {
+            return candidate;
+        }
+        n += 1;
+    }
+}
+
+/// Trimmed category, `None` when blank.
+fn normalize_category(category: Option<String>) -> Option<String> {
+    category
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty())
+}
+
+/// Result of copying external images into a resource folder.
+pub(crate) struct ImportedImages {
+    pub stored: Vec<String>,
+    /// Source path -> stored path, for rewriting prompt template references.
+    pub mapping: HashMap<String, String>,
+    pub failed: Vec<ImageImportFailure>,
+}
+
+/// Copies each image into `resource_dir`, renaming on filename collisions.
+/// Missing or undecodable files are reported instead of aborting the import.
+pub(crate) fn import_images(resource_dir: &Path, paths: &[String]) -> ImportedImages {
+    let mut imported = ImportedImages {
+        stored: Vec::new(),
+        mapping: HashMap::new(),
+        failed: Vec::new(),
+    };
+
+    for img_path in paths {
+        let src = PathBuf::from(img_path);
+        let fail = |reason: String| ImageImportFailure {
+            path: img_path.clone(),
+            reason,
+        };
+
+        if !src.is_file() {
+            imported.failed.push(fail("File not found".to_string()));
+            continue;
+        }
+        if let Err(e) = imaging::validate(&src) {
+            imported
+                .failed
+                .push(fail(format!("Not a readable image: {}", e)));
+            continue;
+        }
+
+        let filename = src
+            .file_name()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| "image".to_string());
+        let dest = unique_destination(resource_dir, &filename);
+        if let Err(e) = fs::copy(&src, &dest) {
+            imported.failed.push(fail(e.to_string()));
+            continue;
+        }
+
+        let stored = dest.to_string_lossy().to_string();
+        imported.mapping.insert(img_path.clone(), stored.clone());
+        imported.stored.push(stored);
+    }
+
+    imported
+}
+
+/// Parses a submitted template and checks that referenced resources exist and
+/// that it does not reference `own_id`.
+pub(crate) fn parse_template(
+    conn: &Connection,
+    raw: &str,
+    own_id: Option<&str>,
+) -> Result<PromptTemplate, String> {
+    let template = PromptTemplate::parse(raw)?;
+    template.validate()?;
+    for id in template.resource_refs() {
+        if Some(id) == own_id {
+            return Err("A resource cannot reference itself".to_string());
+        }
+        if db::resources::get(conn, id)?.is_none() {
+            return Err(format!("Referenced resource {} does not exist", id));
+        }
+    }
+    Ok(template)
+}
+
 #[tauri::command]
 pub async fn create_resource(
     app: AppHandle,
+    db: State<'_, Database>,
     payload: CreateResourcePayload,
-) -> Result<Resource, String> {
+) -> Result<ResourceImportResult, String> {
+    let template = {
+        let conn = db.lock()?;
+        parse_template(&conn, &payload.prompt, None)?
+    };
     let id = Uuid::new_v4().to_string();
     let timestamp = Utc::now().timestamp_millis();
 
-    let doc_dir = app.path().document_dir().map_err(|e| e.to_string())?;
-    let resource_base_dir = doc_dir.join("Oneiria/Resources");
-    let resource_dir = resource_base_dir.join(&id);
+    let resource_dir = resources_dir(&app)?.join(&id);
 
     if !resource_dir.exists() {
         fs::create_dir_all(&resource_dir).map_err(|e| e.to_string())?;
     }
 
-    let mut stored_images = Vec::new();
-    for img_path in payload.image_paths {
-        let src = PathBuf::from(&img_path);
-        if src.exists() {
-            let filename = src.file_name().unwrap_or_default();
-            let dest = resource_dir.join(filename);
-            fs::copy(&src, &dest).map_err(|e| e.to_string())?;
-            stored_images.push(dest.to_string_lossy().to_string());
-        }
-    }
+    let imported = import_images(&resource_dir, &payload.image_paths);
 
     let resource = Resource {
         id: id.clone(),
         name: payload.name,
         description: payload.description,
-        prompt_template: payload.prompt,
-        images: stored_images,
+        prompt_template: template.remap_images(&imported.mapping),
+        images: imported.stored,
+        category: normalize_category(payload.category),
+        tags: normalize_tags(payload.tags.unwrap_or_default()),
+        usage_count: 0,
         created_at: timestamp,
         updated_at: timestamp,
     };
 
-    let store = app.store("resources.json").map_err(|e| e.to_string())?;
+    let mut conn = db.lock()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    db::resources::insert(&tx, &resource)?;
+    tx.commit().map_err(|e| e.to_string())?;
 
-    let mut resources: Vec<Resource> = match store.get("resources") {
-        Some(val) => serde_json::from_value(val.clone()).unwrap_or_default(),
-        None => Vec::new(),
-    };
+    Ok(ResourceImportResult {
+        resource,
+        failed_images: imported.failed,
+    })
+}
 
-    resources.push(resource.clone());
+/// Creates a resource from gallery images. The prompt template is pre-filled with
+/// the stored prompt of the first image that has one, and the name defaults to
+/// the first image's file name.
+#[tauri::command]
+pub async fn create_resource_from_images(
+    app: AppHandle,
+    db: State<'_, Database>,
+    paths: Vec<String>,
+    name: Option<String>,
+) -> Result<ResourceImportResult, String> {
+    if paths.is_empty() {
+        return Err("No images selected".to_string());
+    }
 
-    store.set("resources", serde_json::json!(resources));
-    store.save().map_err(|e| e.to_string())?;
+    let prompt = paths
+        .iter()
+        .filter_map(|path| image_hash(&app, path).ok())
+        .find_map(|hash| generation_record(&app, &hash).ok().flatten())
+        .map(|record| json!([{ "type": "text", "value": record.prompt }]).to_string())
+        .unwrap_or_default();
+    let name = name
+        .map(|n| n.trim().to_string())
+        .filter(|n| !n.is_empty())
+        .or_else(|| {
+            Path::new(&paths[0])
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+        })
+        .unwrap_or_else(|| "Untitled".to_string());
 
-    Ok(resource)
+    create_resource(
+        app,
+        db,
+        CreateResourcePayload {
+            name,
+            description: None,
+            prompt,
+            image_paths: paths,
+            category: None,
+            tags: None,
+        },
+    )
+    .await
 }
 
 #[tauri::command]
-pub async fn list_resources(app: AppHandle) -> Result<Vec<Resource>, String> {
-    let store = app.store("resources.json").map_err(|e| e.to_string())?;
-    match store.get("resources") {
-        Some(val) => serde_json::from_value(val.clone()).map_err(|e| e.to_string()),
-        None => Ok(Vec::new()),
+pub async fn list_resources(db: State<'_, Database>) -> Result<Vec<Resource>, String> {
+    let conn = db.lock()?;
+    db::resources::list(&conn)
+}
+
+/// Resolves the requested image list against the resource folder: images already
+/// in the folder are kept, external ones are copied in.
+fn sync_images(
+    resource_dir: &Path,
+    requested: &[String],
+) -> Result<(Vec<String>, ImportedImages), String> {
+    if !resource_dir.exists() {
+        fs::create_dir_all(resource_dir).map_err(|e| e.to_string())?;
+    }
+
+    let external: Vec<String> = requested
+        .iter()
+        .filter(|p| !Path::new(p).starts_with(resource_dir))
+        .cloned()
+        .collect();
+    let mut imported = import_images(resource_dir, &external);
+
+    let mut images = Vec::new();
+    for path in requested {
+        if Path::new(path).starts_with(resource_dir) {
+            if Path::new(path).is_file() {
+                images.push(path.clone());
+            } else {
+                imported.failed.push(ImageImportFailure {
+                    path: path.clone(),
+                    reason: "File not found".to_string(),
+                });
+            }
+        } else if let Some(stored) = imported.mapping.get(path) {
+            images.push(stored.clone());
+        }
+    }
+    let mut seen = HashSet::new();
+    images.retain(|p| seen.insert(p.clone()));
+
+    Ok((images, imported))
+}
+
+/// Deletes files in the resource folder that the resource no longer references.
+pub(crate) fn remove_orphaned_images(resource_dir: &Path, images: &[String]) {
+    let Ok(entries) = fs::read_dir(resource_dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let referenced = images.iter().any(|img| Path::new(img) == path);
+        if path.is_file() && !referenced {
+            if let Err(e) = fs::remove_file(&path) {
+                log::warn!("Failed to remove {}: {}", path.display(), e);
+            }
+        }
     }
 }
 
-// Placeholder for update/delete to avoid unused imports if I added UpdateResourcePayload
 #[tauri::command]
 pub async fn update_resource(
     app: AppHandle,
+    db: State<'_, Database>,
     payload: UpdateResourcePayload,
-) -> Result<Resource, String> {
-    let store = app.store("resources.json").map_err(|e| e.to_string())?;
-    let mut resources: Vec<Resource> = match store.get("resources") {
-        Some(val) => serde_json::from_value(val.clone()).unwrap_or_default(),
-        None => return Err("No resources found".to_string()),
-    };
+) -> Result<ResourceImportResult, String> {
+    let mut conn = db.lock()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut resource = db::resources::get(&tx, &payload.id)?.ok_or("Resource not found")?;
 
-    if let Some(r) = resources.iter_mut().find(|r| r.id == payload.id) {
-        if let Some(name) = payload.name {
-            r.name = name;
-        }
-        if let Some(desc) = payload.description {
-            r.description = Some(desc);
+    // Reject edits made against a version that has since been replaced
+    if let Some(expected) = payload.expected_updated_at {
+        if expected != resource.updated_at {
+            return Err(format!(
+                "Resource '{}' was changed by another edit. Reload it and try again.",
+                resource.name
+            ));
         }
-        if let Some(prompt) = payload.prompt {
-            r.prompt_template = prompt;
-        }
-        if let Some(images) = payload.images {
-            r.images = images;
-        }
-        r.updated_at = Utc::now().timestamp_millis();
+    }
+
+    let resource_dir = resources_dir(&app)?.join(&resource.id);
+    snapshot(&tx, &resource_dir, &resource)?;
+
+    if let Some(name) = payload.name {
+        resource.name = name;
+    }
+    if let Some(desc) = payload.description {
+        resource.description = Some(desc);
+    }
+    if let Some(prompt) = payload.prompt {
+        resource.prompt_template = parse_template(&tx, &prompt, Some(&resource.id))?;
+    }
+    if let Some(category) = payload.category {
+        resource.category = normalize_category(Some(category));
+    }
+    if let Some(tags) = payload.tags {
+        resource.tags = normalize_tags(tags);
+    }
 
-        let updated_resource = r.clone();
+    let mut failed_images = Vec::new();
+    let images_changed = payload.images.is_some();
+    if let Some(images) = payload.images {
+        let (images, imported) = sync_images(&resource_dir, &images)?;
+        resource.images = images;
+        resource.prompt_template = resource.prompt_template.remap_images(&imported.mapping);
+        failed_images = imported.failed;
+    }
+    // Image references must point at the resource's own images, so drop any
+    // whose image was removed or failed to import
+    let images = resource.images.clone();
+    resource
+        .prompt_template
+        .retain_images(|path| images.iter().any(|img| img == path));
+    // Strictly increasing, so two edits within the same millisecond stay distinguishable
+    resource.updated_at = Utc::now().timestamp_millis().max(resource.updated_at + 1);
 
-        store.set("resources", serde_json::json!(resources));
-        store.save().map_err(|e| e.to_string())?;
+    db::resources::update(&tx, &resource)?;
+    tx.commit().map_err(|e| e.to_string())?;
 
-        Ok(updated_resource)
-    } else {
-        Err("Resource not found".to_string())
+    if images_changed {
+        remove_orphaned_images(&resource_dir, &resource.images);
     }
+
+    Ok(ResourceImportResult {
+        resource,
+        failed_images,
+    })
 }
 
+/// Copies a resource and its images under a fresh ID, pointing the copy's prompt
+/// template at the copied images. History and usage count are not carried over.
 #[tauri::command]
-pub async fn delete_resource(app: AppHandle, id: String) -> Result<(), String> {
-    let store = app.store("resources.json").map_err(|e| e.to_string())?;
-    let mut resources: Vec<Resource> = match store.get("resources") {
-        Some(val) => serde_json::from_value(val.clone()).unwrap_or_default(),
-        None => return Ok(()),
+pub async fn duplicate_resource(
+    app: AppHandle,
+    db: State<'_, Database>,
+    id: String,
+    new_name: Option<String>,
+) -> Result<ResourceImportResult, String> {
+    let mut conn = db.lock()?;
+    let source = db::resources::get(&conn, &id)?.ok_or("Resource not found")?;
+
+    let new_id = Uuid::new_v4().to_string();
+    let timestamp = Utc::now().timestamp_millis();
+    let resource_dir = resources_dir(&app)?.join(&new_id);
+    fs::create_dir_all(&resource_dir).map_err(|e| e.to_string())?;
+
+    let imported = import_images(&resource_dir, &source.images);
+
+    let name = new_name
+        .map(|n| n.trim().to_string())
+        .filter(|n| !n.is_empty())
+        .unwrap_or_else(|| format!("{} (copy)", source.name));
+    let mut prompt_template = source.prompt_template.remap_images(&imported.mapping);
+    // Images that failed to copy would otherwise still point into the source
+    prompt_template.retain_images(|path| imported.stored.iter().any(|img| img == path));
+    let resource = Resource {
+        id: new_id,
+        name,
+        prompt_template,
+        images: imported.stored,
+        usage_count: 0,
+        created_at: timestamp,
+        updated_at: timestamp,
+        ..source
     };
 
-    // Should also delete files?
-    // "Delete Resource ... exclusive files".
-    // We should delete the folder `Oneiria/Resources/{id}`.
-    let doc_dir = app.path().document_dir().map_err(|e| e.to_string())?;
-    let resource_dir = doc_dir.join("Oneiria/Resources").join(&id);
-    if resource_dir.exists() {
-        let _ = fs::remove_dir_all(resource_dir); // ignore error
-    }
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    db::resources::insert(&tx, &resource)?;
+    tx.commit().map_err(|e| e.to_string())?;
 
-    resources.retain(|r| r.id != id);
+    Ok(ResourceImportResult {
+        resource,
+        failed_images: imported.failed,
+    })
+}
 
-    store.set("resources", serde_json::json!(resources));
-    store.save().map_err(|e| e.to_string())?;
+/// Moves the resource and its folder to the trash so it can be restored.
+#[tauri::command]
+pub async fn delete_resource(
+    app: AppHandle,
+    db: State<'_, Database>,
+    id: String,
+) -> Result<(), String> {
+    let mut conn = db.lock()?;
+    let Some(resource) = db::resources::get(&conn, &id)? else {
+        return Ok(());
+    };
+    // Deleting the record cascades to its versions, so keep them with the trash entry
+    let versions = db::versions::list(&conn, &id)?;
 
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    db::resources::delete(&tx, &id)?;
+    // Nothing has moved if this fails, and the delete rolls back with the transaction
+    let item = trash_resource(&app, &resource, versions)?;
+    if let Err(e) = tx.commit() {
+        if let Err(undo) = untrash_resource(&app, &item) {
+            log::error!(
+                "Failed to move {} back from the trash: {}",
+                resource.id,
+                undo
+            );
+        }
+        return Err(e.to_string());
+    }
     Ok(())
 }
+
+#[tauri::command]
+pub async fn list_resource_tags(db: State<'_, Database>) -> Result<Vec<String>, String> {
+    let conn = db.lock()?;
+    db::resources::list_tags(&conn)
+}
+
+#[tauri::command]
+pub async fn list_resource_categories(db: State<'_, Database>) -> Result<Vec<String>, String> {
+    let conn = db.lock()?;
+    db::resources::list_categories(&conn)
+}
+
+/// The resource's prompt template as final prompt text, nested resources expanded.
+#[tauri::command]
+pub async fn render_resource_prompt(db: State<'_, Database>, id: String) -> Result<String, String> {
+    let conn = db.lock()?;
+    if db::resources::get(&conn, &id)?.is_none() {
+        return Err("Resource not found".to_string());
+    }
+    let content = [PromptContent::ResourceReference(id)];
+    Ok(prompt::render::render(&conn, &content, &[])?.prompt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn unique_destination_keeps_a_free_name() {
+        let dir = TempDir::new("resources-free");
+        assert_eq!(unique_destination(&dir, "face.png"), dir.join("face.png"));
+    }
+
+    #[test]
+    fn unique_destination_numbers_taken_names() {
+        let dir = TempDir::new("resources-taken");
+        fs::write(dir.join("face.png"), b"").unwrap();
+        assert_eq!(unique_destination(&dir, "face.png"), dir.join("face_1.png"));
+        fs::write(dir.join("face_1.png"), b"").unwrap();
+        assert_eq!(unique_destination(&dir, "face.png"), dir.join("face_2.png"));
+
+        fs::write(dir.join("notes"), b"").unwrap();
+        assert_eq!(unique_destination(&dir, "notes"), dir.join("notes_1"));
+        fs::write(dir.join("a.tar.gz"), b"").unwrap();
+        assert_eq!(unique_destination(&dir, "a.tar.gz"), dir.join("a.tar_1.gz"));
+    }
+
+    #[test]
+    fn normalize_category_drops_blank_values() {
+        assert_eq!(
+            normalize_category(Some("  Faces ".to_string())).as_deref(),
+            Some("Faces")
+        );
+        assert_eq!(normalize_category(Some("   ".to_string())), None);
+        assert_eq!(normalize_category(None), None);
+    }
+}
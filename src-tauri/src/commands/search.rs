@@ -0,0 +1,145 @@
+use crate::commands::generate::generation_record;
+use crate::commands::metadata::hashed_gallery;
+use crate::commands::resources::load_resources;
+use crate::db::{self, Database};
+use crate::models::{GeneratedImage, Resource, ResourceQuery, ResourceSort};
+use crate::search::{rank, Field};
+use std::collections::HashMap;
+use tauri::{AppHandle, State};
+
+const DEFAULT_SEARCH_LIMIT: usize = 100;
+
+struct SearchDocument {
+    image: GeneratedImage,
+    prompt: String,
+    negative_prompt: String,
+    resource_names: String,
+}
+
+/// Full-text search over the stored prompts, negative prompts and referenced
+/// resource names of gallery images, ranked by relevance.
+#[tauri::command]
+pub async fn search_gallery_images(
+    app: AppHandle,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<GeneratedImage>, String> {
+    let resource_names: HashMap<String, String> = load_resources(&app)?
+        .into_iter()
+        .map(|r| (r.id, r.name))
+        .collect();
+
+    let mut documents = Vec::new();
+    for (hash, image) in hashed_gallery(&app)? {
+        let Some(record) = generation_record(&app, &hash)? else {
+            continue;
+        };
+        documents.push(SearchDocument {
+            image,
+            prompt: record.prompt,
+            negative_prompt: record.negative_prompt.unwrap_or_default(),
+            resource_names: record
+                .resource_ids
+                .iter()
+                .filter_map(|id| resource_names.get(id).cloned())
+                .collect::<Vec<_>>()
+                .join(" "),
+        });
+    }
+
+    let fields: Vec<Vec<Field>> = documents
+        .iter()
+        .map(|doc| {
+            vec![
+                Field {
+                    text: &doc.prompt,
+                    weight: 1.0,
+                },
+                Field {
+                    text: &doc.negative_prompt,
+                    weight: 0.5,
+                },
+                Field {
+                    text: &doc.resource_names,
+                    weight: 1.5,
+                },
+            ]
+        })
+        .collect();
+
+    Ok(rank(&fields, &query)
+        .into_iter()
+        .take(limit.unwrap_or(DEFAULT_SEARCH_LIMIT))
+        .map(|(i, _)| documents[i].image.clone())
+        .collect())
+}
+
+/// Filters resources by category and tags, then matches `text` against name,
+/// description, prompt template and tags. See `ResourceQuery` for ordering.
+#[tauri::command]
+pub async fn search_resources(
+    db: State<'_, Database>,
+    query: ResourceQuery,
+) -> Result<Vec<Resource>, String> {
+    let mut resources = {
+        let conn = db.lock()?;
+        db::resources::list(&conn)?
+    };
+
+    if let Some(category) = &query.category {
+        resources.retain(|r| r.category.as_deref() == Some(category.trim()));
+    }
+    if let Some(tags) = &query.tags {
+        resources.retain(|r| tags.iter().all(|t| r.tags.contains(&t.trim().to_string())));
+    }
+
+    let text = query.text.as_deref().map(str::trim).unwrap_or("");
+    if !text.is_empty() {
+        let tags: Vec<String> = resources.iter().map(|r| r.tags.join(" ")).collect();
+        let prompts: Vec<String> = resources
+            .iter()
+            .map(|r| r.prompt_template.plain_text())
+            .collect();
+        let fields: Vec<Vec<Field>> = resources
+            .iter()
+            .zip(tags.iter().zip(&prompts))
+            .map(|(r, (tags, prompt))| {
+                vec![
+                    Field {
+                        text: &r.name,
+                        weight: 2.0,
+                    },
+                    Field {
+                        text: r.description.as_deref().unwrap_or(""),
+                        weight: 1.0,
+                    },
+                    Field {
+                        text: prompt,
+                        weight: 0.8,
+                    },
+                    Field {
+                        text: tags,
+                        weight: 1.5,
+                    },
+                ]
+            })
+            .collect();
+        let ranked: Vec<usize> = rank(&fields, text).into_iter().map(|(i, _)| i).collect();
+        let mut slots: Vec<Option<Resource>> = resources.into_iter().map(Some).collect();
+        resources = ranked.into_iter().filter_map(|i| slots[i].take()).collect();
+    }
+
+    if let Some(sort) = query.sort {
+        match sort {
+            ResourceSort::Name => resources.sort_by_key(|r| r.name.to_lowercase()),
+            ResourceSort::Created => resources.sort_by_key(|r| r.created_at),
+            ResourceSort::Updated => resources.sort_by_key(|r| r.updated_at),
+            ResourceSort::Usage => resources.sort_by_key(|r| r.usage_count),
+        }
+        if query.descending.unwrap_or(false) {
+            resources.reverse();
+        }
+    }
+
+    Ok(resources)
+}
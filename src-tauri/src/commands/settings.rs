@@ -11,6 +11,7 @@ pub struct AppSettings {
     pub zhipu_api_key: Option<String>,
     pub zhipu_watermark: Option<bool>,
     pub theme: Option<String>,
+    pub trash_retention_days: Option<u32>,
 }
 
 #[tauri::command]
@@ -35,6 +36,10 @@ pub async fn save_settings(app: AppHandle, settings: AppSettings) -> Result<(),
         store.set("theme", json!(theme));
     }
 
+    if let Some(days) = settings.trash_retention_days {
+        store.set("trash_retention_days", json!(days));
+    }
+
     store.save().map_err(|e| e.to_string())?;
     Ok(())
 }
@@ -62,11 +67,17 @@ pub async fn get_settings(app: AppHandle) -> Result<AppSettings, String> {
         .get("theme")
         .and_then(|v| v.as_str().map(|s| s.to_string()));
 
+    let trash_retention_days = store
+        .get("trash_retention_days")
+        .and_then(|v| v.as_u64())
+        .map(|v| v as u32);
+
     Ok(AppSettings {
         provider,
         doubao_api_key,
         zhipu_api_key,
         zhipu_watermark,
         theme,
+        trash_retention_days,
     })
 }
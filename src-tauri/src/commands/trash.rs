@@ -0,0 +1,371 @@
+use crate::commands::resources::resources_dir;
+use crate::db::{self, Database};
+use crate::models::{Resource, ResourceVersion, TrashItem, TrashItemKind};
+use chrono::Utc;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_store::StoreExt;
+use uuid::Uuid;
+
+const TRASH_STORE: &str = "trash.json";
+const DEFAULT_RETENTION_DAYS: u64 = 30;
+
+fn trash_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?
+        .join("Trash"))
+}
+
+fn load_trash(app: &AppHandle) -> Result<Vec<TrashItem>, String> {
+    let store = app.store(TRASH_STORE).map_err(|e| e.to_string())?;
+    match store.get("items") {
+        Some(val) => serde_json::from_value(val).map_err(|e| e.to_string()),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn save_trash(app: &AppHandle, items: &[TrashItem]) -> Result<(), String> {
+    let store = app.store(TRASH_STORE).map_err(|e| e.to_string())?;
+    store.set("items", serde_json::json!(items));
+    store.save().map_err(|e| e.to_string())
+}
+
+/// Retention period from settings; 0 disables automatic purging.
+fn retention_days(app: &AppHandle) -> Result<u64, String> {
+    let store = app.store("settings.json").map_err(|e| e.to_string())?;
+    Ok(store
+        .get("trash_retention_days")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(DEFAULT_RETENTION_DAYS))
+}
+
+fn copy_dir_all(src: &Path, dst: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(dst)?;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let target = dst.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir_all(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Renames `src` to `dst`, falling back to copy + delete when they live on
+/// different volumes (e.g. Documents and the app data dir).
+pub(crate) fn move_path(src: &Path, dst: &Path) -> Result<(), String> {
+    if let Some(parent) = dst.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    if fs::rename(src, dst).is_ok() {
+        return Ok(());
+    }
+
+    if src.is_dir() {
+        copy_dir_all(src, dst).map_err(|e| e.to_string())?;
+        fs::remove_dir_all(src).map_err(|e| e.to_string())
+    } else {
+        fs::copy(src, dst).map_err(|e| e.to_string())?;
+        fs::remove_file(src).map_err(|e| e.to_string())
+    }
+}
+
+/// Picks a free path next to `path` when restoring over an existing file.
+fn available_path(path: &Path) -> PathBuf {
+    if !path.exists() {
+        return path.to_path_buf();
+    }
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let ext = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+
+    let mut n = 1;
+    loop {
+        let candidate = path.with_file_name(format!("{}_restored_{}{}", stem, n, ext));
+        if !candidate.exists() {
+            return candidate;
+        }
+        n += 1;
+    }
+}
+
+/// Moves a file or folder into the trash under its own entry.
+fn trash_path(app: &AppHandle, path: &Path, kind: TrashItemKind) -> Result<TrashItem, String> {
+    let id = Uuid::new_v4().to_string();
+    let filename = path.file_name().ok_or("Invalid path")?;
+    let trashed_path = trash_dir(app)?.join(&id).join(filename);
+    move_path(path, &trashed_path)?;
+
+    let item = TrashItem {
+        id,
+        kind,
+        name: filename.to_string_lossy().to_string(),
+        original_path: path.to_string_lossy().to_string(),
+        trashed_path: trashed_path.to_string_lossy().to_string(),
+        resource: None,
+        versions: Vec::new(),
+        deleted_at: Utc::now().timestamp_millis(),
+    };
+
+    let mut items = load_trash(app)?;
+    items.push(item.clone());
+    save_trash(app, &items)?;
+
+    Ok(item)
+}
+
+/// Moves a gallery image into the trash.
+pub(crate) fn trash_image(app: &AppHandle, path: &Path) -> Result<TrashItem, String> {
+    if !path.is_file() {
+        return Err(format!("Image not found: {}", path.display()));
+    }
+    trash_path(app, path, TrashItemKind::Image)
+}
+
+/// Moves a folder that belongs to no resource into the trash.
+pub(crate) fn trash_folder(app: &AppHandle, path: &Path) -> Result<TrashItem, String> {
+    if !path.is_dir() {
+        return Err(format!("Folder not found: {}", path.display()));
+    }
+    trash_path(app, path, TrashItemKind::Folder)
+}
+
+/// Moves a resource folder into the trash along with a snapshot of its record
+/// and version history.
+pub(crate) fn trash_resource(
+    app: &AppHandle,
+    resource: &Resource,
+    versions: Vec<ResourceVersion>,
+) -> Result<TrashItem, String> {
+    let id = Uuid::new_v4().to_string();
+    let resource_dir = resources_dir(app)?.join(&resource.id);
+    let trashed_path = trash_dir(app)?.join(&id);
+
+    if resource_dir.exists() {
+        move_path(&resource_dir, &trashed_path)?;
+    } else {
+        fs::create_dir_all(&trashed_path).map_err(|e| e.to_string())?;
+    }
+
+    let item = TrashItem {
+        id,
+        kind: TrashItemKind::Resource,
+        name: resource.name.clone(),
+        original_path: resource_dir.to_string_lossy().to_string(),
+        trashed_path: trashed_path.to_string_lossy().to_string(),
+        resource: Some(resource.clone()),
+        versions,
+        deleted_at: Utc::now().timestamp_millis(),
+    };
+
+    let mut items = load_trash(app)?;
+    items.push(item.clone());
+    save_trash(app, &items)?;
+
+    Ok(item)
+}
+
+/// Undoes `trash_resource` when the database change it went with fails: moves
+/// the folder back and drops the trash entry.
+pub(crate) fn untrash_resource(app: &AppHandle, item: &TrashItem) -> Result<(), String> {
+    let trashed_path = Path::new(&item.trashed_path);
+    if trashed_path.exists() {
+        move_path(trashed_path, Path::new(&item.original_path))?;
+    }
+    let mut items = load_trash(app)?;
+    items.retain(|i| i.id != item.id);
+    save_trash(app, &items)
+}
+
+fn remove_trashed_files(app: &AppHandle, item: &TrashItem) -> Result<(), String> {
+    let item_dir = trash_dir(app)?.join(&item.id);
+    if item_dir.exists() {
+        fs::remove_dir_all(&item_dir).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Splits items into those deleted more than `days` before `now` and the rest.
+fn split_expired(items: Vec<TrashItem>, days: u64, now: i64) -> (Vec<TrashItem>, Vec<TrashItem>) {
+    let cutoff = now - (days as i64) * 24 * 60 * 60 * 1000;
+    items.into_iter().partition(|item| item.deleted_at < cutoff)
+}
+
+/// Moves a trashed file or folder back to its original path, or next to it if
+/// that is taken.
+fn restore_path(item: &TrashItem) -> Result<PathBuf, String> {
+    let dest = available_path(Path::new(&item.original_path));
+    move_path(Path::new(&item.trashed_path), &dest)?;
+    Ok(dest)
+}
+
+/// Permanently deletes trash items older than the configured retention period.
+pub(crate) fn purge_expired(app: &AppHandle) -> Result<usize, String> {
+    let days = retention_days(app)?;
+    if days == 0 {
+        return Ok(0);
+    }
+    let (expired, kept) = split_expired(load_trash(app)?, days, Utc::now().timestamp_millis());
+    if expired.is_empty() {
+        return Ok(0);
+    }
+
+    for item in &expired {
+        remove_trashed_files(app, item)?;
+    }
+    save_trash(app, &kept)?;
+
+    Ok(expired.len())
+}
+
+#[tauri::command]
+pub async fn list_trash(app: AppHandle) -> Result<Vec<TrashItem>, String> {
+    purge_expired(&app)?;
+    let mut items = load_trash(&app)?;
+    items.sort_by_key(|item| std::cmp::Reverse(item.deleted_at));
+    Ok(items)
+}
+
+#[tauri::command]
+pub async fn restore_trash_item(app: AppHandle, id: String) -> Result<TrashItem, String> {
+    let mut items = load_trash(&app)?;
+    let index = items
+        .iter()
+        .position(|item| item.id == id)
+        .ok_or("Trash item not found")?;
+    let item = items[index].clone();
+    let trashed_path = PathBuf::from(&item.trashed_path);
+
+    match item.kind {
+        TrashItemKind::Image | TrashItemKind::Folder => {
+            restore_path(&item)?;
+        }
+        TrashItemKind::Resource => {
+            let resource = item
+                .resource
+                .clone()
+                .ok_or("Trash item has no resource record")?;
+            let mut conn = app.state::<Database>().inner().lock()?;
+            if db::resources::get(&conn, &resource.id)?.is_some() {
+                return Err(format!("Resource '{}' already exists", resource.name));
+            }
+
+            let dest = resources_dir(&app)?.join(&resource.id);
+            if dest.exists() {
+                return Err(format!(
+                    "Resource folder already exists: {}",
+                    dest.display()
+                ));
+            }
+            let tx = conn.transaction().map_err(|e| e.to_string())?;
+            db::resources::insert(&tx, &resource)?;
+            for version in &item.versions {
+                db::versions::insert(&tx, version)?;
+            }
+            move_path(&trashed_path, &dest)?;
+            if let Err(e) = tx.commit() {
+                let _ = move_path(&dest, &trashed_path);
+                return Err(e.to_string());
+            }
+        }
+    }
+
+    remove_trashed_files(&app, &item)?;
+    items.remove(index);
+    save_trash(&app, &items)?;
+
+    Ok(item)
+}
+
+#[tauri::command]
+pub async fn empty_trash(app: AppHandle) -> Result<(), String> {
+    for item in load_trash(&app)? {
+        remove_trashed_files(&app, &item)?;
+    }
+    save_trash(&app, &[])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    const DAY: i64 = 24 * 60 * 60 * 1000;
+
+    fn item(id: &str, original: &Path, trashed: &Path, deleted_at: i64) -> TrashItem {
+        TrashItem {
+            id: id.to_string(),
+            kind: TrashItemKind::Image,
+            name: id.to_string(),
+            original_path: original.to_string_lossy().to_string(),
+            trashed_path: trashed.to_string_lossy().to_string(),
+            resource: None,
+            versions: Vec::new(),
+            deleted_at,
+        }
+    }
+
+    #[test]
+    fn available_path_numbers_taken_names() {
+        let dir = TempDir::new("trash-available");
+        let path = dir.join("face.png");
+        assert_eq!(available_path(&path), path);
+        fs::write(&path, b"").unwrap();
+        assert_eq!(available_path(&path), dir.join("face_restored_1.png"));
+        fs::write(dir.join("face_restored_1.png"), b"").unwrap();
+        assert_eq!(available_path(&path), dir.join("face_restored_2.png"));
+
+        fs::create_dir(dir.join("folder")).unwrap();
+        assert_eq!(
+            available_path(&dir.join("folder")),
+            dir.join("folder_restored_1")
+        );
+    }
+
+    #[test]
+    fn only_items_past_retention_expire() {
+        let dir = Path::new("/trash");
+        let now = 100 * DAY;
+        let items = vec![
+            item("old", dir, dir, now - 31 * DAY),
+            item("recent", dir, dir, now - 29 * DAY),
+            item("new", dir, dir, now),
+        ];
+        let (expired, kept) = split_expired(items, 30, now);
+        let ids = |items: &[TrashItem]| items.iter().map(|i| i.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&expired), vec!["old"]);
+        assert_eq!(ids(&kept), vec!["recent", "new"]);
+    }
+
+    #[test]
+    fn restore_moves_back_without_overwriting() {
+        let dir = TempDir::new("trash-restore");
+        let original = dir.join("gallery").join("face.png");
+        let trashed = dir.join("trash").join("face.png");
+        fs::create_dir_all(original.parent().unwrap()).unwrap();
+        fs::create_dir_all(trashed.parent().unwrap()).unwrap();
+
+        fs::write(&trashed, b"first").unwrap();
+        let first = item("first", &original, &trashed, 0);
+        assert_eq!(restore_path(&first).unwrap(), original);
+        assert!(!trashed.exists());
+
+        // A new file took the original name in the meantime
+        fs::write(&trashed, b"second").unwrap();
+        let second = item("second", &original, &trashed, 0);
+        let restored = restore_path(&second).unwrap();
+        assert_eq!(restored, dir.join("gallery").join("face_restored_1.png"));
+        assert_eq!(fs::read(&original).unwrap(), b"first");
+        assert_eq!(fs::read(&restored).unwrap(), b"second");
+    }
+}
@@ -0,0 +1,283 @@
+use crate::commands::resources::{remove_orphaned_images, resources_dir, unique_destination};
+use crate::db::{self, Database};
+use crate::models::{DiffKind, DiffLine, PromptContent, PromptTemplate, Resource, ResourceVersion};
+use chrono::Utc;
+use rusqlite::Connection;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use tauri::{AppHandle, State};
+
+/// Per-resource folder holding the images of earlier versions, one subfolder per
+/// version. Dot-prefixed so it is never mistaken for a resource image.
+pub(crate) const VERSIONS_DIR: &str = ".versions";
+
+/// Hard-links `src` to `dst` so unchanged images cost no extra space, copying
+/// when linking is not possible.
+fn link_or_copy(src: &Path, dst: &Path) -> std::io::Result<()> {
+    fs::hard_link(src, dst).or_else(|_| fs::copy(src, dst).map(|_| ()))
+}
+
+/// Records the current state of `resource` as its next version, keeping its
+/// images under `.versions/{n}` so later updates cannot remove them.
+/// Call inside the transaction that replaces the resource.
+pub(crate) fn snapshot(
+    conn: &Connection,
+    resource_dir: &Path,
+    resource: &Resource,
+) -> Result<ResourceVersion, String> {
+    let version = db::versions::next_version(conn, &resource.id)?;
+    let version_dir = resource_dir.join(VERSIONS_DIR).join(version.to_string());
+    // Leftovers from a snapshot whose transaction never committed
+    if version_dir.exists() {
+        fs::remove_dir_all(&version_dir).map_err(|e| e.to_string())?;
+    }
+    fs::create_dir_all(&version_dir).map_err(|e| e.to_string())?;
+
+    let mut mapping = HashMap::new();
+    let mut images = Vec::new();
+    for image in &resource.images {
+        let src = Path::new(image);
+        let Some(filename) = src.file_name() else {
+            continue;
+        };
+        let dest = unique_destination(&version_dir, &filename.to_string_lossy());
+        if let Err(e) = link_or_copy(src, &dest) {
+            log::warn!(
+                "Version {} of {} misses {}: {}",
+                version,
+                resource.id,
+                image,
+                e
+            );
+            continue;
+        }
+        let stored = dest.to_string_lossy().to_string();
+        mapping.insert(image.clone(), stored.clone());
+        images.push(stored);
+    }
+
+    let snapshot = ResourceVersion {
+        resource_id: resource.id.clone(),
+        version,
+        name: resource.name.clone(),
+        description: resource.description.clone(),
+        prompt_template: resource.prompt_template.remap_images(&mapping),
+        images,
+        created_at: resource.updated_at,
+    };
+    db::versions::insert(conn, &snapshot)?;
+    Ok(snapshot)
+}
+
+/// Human-readable form of a prompt template for diffing: text as-is, references
+/// as bracketed placeholders.
+fn template_lines(template: &PromptTemplate) -> Vec<String> {
+    let text: String = template
+        .0
+        .iter()
+        .map(|item| match item {
+            PromptContent::Text(text) => text.clone(),
+            PromptContent::ImageReference(path) => {
+                let filename = Path::new(path)
+                    .file_name()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_else(|| path.clone());
+                format!("[image: {}]", filename)
+            }
+            PromptContent::ResourceReference(id) => format!("[resource: {}]", id),
+        })
+        .collect();
+    text.lines().map(str::to_string).collect()
+}
+
+/// Line diff from `old` to `new` via longest common subsequence.
+fn diff_lines(old: &[String], new: &[String]) -> Vec<DiffLine> {
+    let (n, m) = (old.len(), new.len());
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let line = |kind, text: &String| DiffLine {
+        kind,
+        text: text.clone(),
+    };
+    let mut diff = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if old[i] == new[j] {
+            diff.push(line(DiffKind::Unchanged, &old[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            diff.push(line(DiffKind::Removed, &old[i]));
+            i += 1;
+        } else {
+            diff.push(line(DiffKind::Added, &new[j]));
+            j += 1;
+        }
+    }
+    diff.extend(old[i..].iter().map(|t| line(DiffKind::Removed, t)));
+    diff.extend(new[j..].iter().map(|t| line(DiffKind::Added, t)));
+    diff
+}
+
+fn version_template(
+    conn: &Connection,
+    id: &str,
+    version: Option<i64>,
+) -> Result<PromptTemplate, String> {
+    match version {
+        Some(version) => Ok(db::versions::get(conn, id, version)?
+            .ok_or_else(|| format!("Version {} not found", version))?
+            .prompt_template),
+        None => Ok(db::resources::get(conn, id)?
+            .ok_or("Resource not found")?
+            .prompt_template),
+    }
+}
+
+/// Earlier versions of a resource, newest first.
+#[tauri::command]
+pub async fn list_resource_versions(
+    db: State<'_, Database>,
+    id: String,
+) -> Result<Vec<ResourceVersion>, String> {
+    let conn = db.lock()?;
+    db::versions::list(&conn, &id)
+}
+
+/// Diffs the prompt template of version `from` against version `to`, or against
+/// the current resource when `to` is omitted.
+#[tauri::command]
+pub async fn diff_resource_versions(
+    db: State<'_, Database>,
+    id: String,
+    from: i64,
+    to: Option<i64>,
+) -> Result<Vec<DiffLine>, String> {
+    let conn = db.lock()?;
+    let old = version_template(&conn, &id, Some(from))?;
+    let new = version_template(&conn, &id, to)?;
+    Ok(diff_lines(&template_lines(&old), &template_lines(&new)))
+}
+
+/// Makes an earlier version current again. The state being replaced is itself
+/// snapshotted first, so a restore can be undone.
+#[tauri::command]
+pub async fn restore_resource_version(
+    app: AppHandle,
+    db: State<'_, Database>,
+    id: String,
+    version: i64,
+) -> Result<Resource, String> {
+    let mut conn = db.lock()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut resource = db::resources::get(&tx, &id)?.ok_or("Resource not found")?;
+    let target = db::versions::get(&tx, &id, version)?
+        .ok_or_else(|| format!("Version {} not found", version))?;
+
+    let resource_dir = resources_dir(&app)?.join(&id);
+    snapshot(&tx, &resource_dir, &resource)?;
+
+    // Bring the version's images back into the resource folder, reusing live
+    // files that are identical.
+    let mut mapping = HashMap::new();
+    let mut images = Vec::new();
+    for image in &target.images {
+        let src = Path::new(image);
+        let Some(filename) = src.file_name() else {
+            continue;
+        };
+        let live = resource_dir.join(filename);
+        let dest = match (fs::read(&live), fs::read(src)) {
+            (Ok(a), Ok(b)) if a == b => live,
+            _ => {
+                let dest = unique_destination(&resource_dir, &filename.to_string_lossy());
+                link_or_copy(src, &dest).map_err(|e| e.to_string())?;
+                dest
+            }
+        };
+        let stored = dest.to_string_lossy().to_string();
+        mapping.insert(image.clone(), stored.clone());
+        images.push(stored);
+    }
+
+    resource.name = target.name;
+    resource.description = target.description;
+    resource.prompt_template = target.prompt_template.remap_images(&mapping);
+    resource.images = images;
+    resource.updated_at = Utc::now().timestamp_millis().max(resource.updated_at + 1);
+
+    db::resources::update(&tx, &resource)?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    remove_orphaned_images(&resource_dir, &resource.images);
+    Ok(resource)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(str::to_string).collect()
+    }
+
+    fn render(diff: &[DiffLine]) -> Vec<String> {
+        diff.iter()
+            .map(|line| {
+                let mark = match line.kind {
+                    DiffKind::Unchanged => ' ',
+                    DiffKind::Added => '+',
+                    DiffKind::Removed => '-',
+                };
+                format!("{}{}", mark, line.text)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn diff_lines_of_identical_text_is_unchanged() {
+        let text = lines("a\nb\nc");
+        assert_eq!(render(&diff_lines(&text, &text)), vec![" a", " b", " c"]);
+    }
+
+    #[test]
+    fn diff_lines_marks_replaced_lines() {
+        let diff = diff_lines(&lines("a\nb\nc"), &lines("a\nx\nc"));
+        assert_eq!(render(&diff), vec![" a", "-b", "+x", " c"]);
+    }
+
+    #[test]
+    fn diff_lines_handles_insertions_and_deletions_at_the_edges() {
+        let diff = diff_lines(&lines("b\nc"), &lines("a\nb"));
+        assert_eq!(render(&diff), vec!["+a", " b", "-c"]);
+        assert_eq!(render(&diff_lines(&[], &lines("a"))), vec!["+a"]);
+        assert_eq!(render(&diff_lines(&lines("a"), &[])), vec!["-a"]);
+    }
+
+    #[test]
+    fn template_lines_show_references_as_placeholders() {
+        let template = PromptTemplate(vec![
+            PromptContent::Text("a portrait of ".to_string()),
+            PromptContent::ImageReference("/res/abc/face.png".to_string()),
+            PromptContent::Text("\nin the style of ".to_string()),
+            PromptContent::ResourceReference("123".to_string()),
+        ]);
+        assert_eq!(
+            template_lines(&template),
+            vec![
+                "a portrait of [image: face.png]",
+                "in the style of [resource: 123]"
+            ]
+        );
+    }
+}
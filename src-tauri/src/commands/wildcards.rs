@@ -0,0 +1,116 @@
+use crate::commands::generate::generate_image;
+use crate::models::{
+    ExpandPromptPayload, GeneratePayload, GenerationProgress, PromptExpansion, QueuedGenerations,
+    WildcardBatchPayload,
+};
+use crate::prompt::wildcards;
+use chrono::Utc;
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Emitter, Manager};
+use uuid::Uuid;
+
+/// Folder of `name.txt` files used by `__name__` wildcards.
+fn wildcards_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let doc_dir = app.path().document_dir().map_err(|e| e.to_string())?;
+    Ok(doc_dir.join("Oneiria/Wildcards"))
+}
+
+fn expand(app: &AppHandle, payload: &ExpandPromptPayload) -> Result<PromptExpansion, String> {
+    let seed = payload
+        .seed
+        .unwrap_or_else(|| Utc::now().timestamp_nanos_opt().unwrap_or_default() as u64);
+    let prompts = wildcards::expand(
+        &payload.prompt,
+        &wildcards_dir(app)?,
+        payload.mode.unwrap_or_default(),
+        payload.count,
+        seed,
+    )?;
+    Ok(PromptExpansion { prompts, seed })
+}
+
+/// Names of the available wildcard files, for `__name__` completion.
+#[tauri::command]
+pub async fn list_wildcards(app: AppHandle) -> Result<Vec<String>, String> {
+    let dir = wildcards_dir(&app)?;
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Ok(Vec::new());
+    };
+    let mut names: Vec<String> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "txt"))
+        .filter_map(|p| p.file_stem().map(|s| s.to_string_lossy().to_string()))
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+#[tauri::command]
+pub async fn expand_prompt(
+    app: AppHandle,
+    payload: ExpandPromptPayload,
+) -> Result<PromptExpansion, String> {
+    expand(&app, &payload)
+}
+
+/// Expands the prompt and runs one generation per expansion in the background,
+/// one at a time. Progress is reported through `generation-progress` events.
+#[tauri::command]
+pub async fn queue_wildcard_generations(
+    app: AppHandle,
+    payload: WildcardBatchPayload,
+) -> Result<QueuedGenerations, String> {
+    let expansion = expand(
+        &app,
+        &ExpandPromptPayload {
+            prompt: payload.payload.prompt.clone(),
+            mode: payload.mode,
+            count: payload.count,
+            seed: payload.seed,
+        },
+    )?;
+    let batch_id = Uuid::new_v4().to_string();
+
+    let queued = QueuedGenerations {
+        batch_id: batch_id.clone(),
+        prompts: expansion.prompts.clone(),
+        seed: expansion.seed,
+    };
+
+    let base = payload.payload;
+    tauri::async_runtime::spawn(async move {
+        let total = expansion.prompts.len();
+        for (i, prompt) in expansion.prompts.into_iter().enumerate() {
+            let result = generate_image(
+                app.clone(),
+                GeneratePayload {
+                    prompt: prompt.clone(),
+                    ..base.clone()
+                },
+            )
+            .await;
+            if let Err(e) = &result {
+                log::warn!("Queued generation {}/{} failed: {}", i + 1, total, e);
+            }
+            let (paths, error) = match result {
+                Ok(paths) => (paths, None),
+                Err(e) => (Vec::new(), Some(e)),
+            };
+            let _ = app.emit(
+                "generation-progress",
+                GenerationProgress {
+                    batch_id: batch_id.clone(),
+                    current: i + 1,
+                    total,
+                    prompt,
+                    paths,
+                    error,
+                },
+            );
+        }
+    });
+
+    Ok(queued)
+}
@@ -0,0 +1,330 @@
+//! Embedded SQLite database in the app data directory.
+
+pub mod resources;
+pub mod versions;
+
+use crate::models::{PromptTemplate, Resource};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use tauri::{AppHandle, Manager};
+
+const DB_FILENAME: &str = "oneiria.db";
+
+/// Schema migrations, applied in order. `PRAGMA user_version` records how many
+/// have run, so never edit or reorder an entry once released; append instead.
+const MIGRATIONS: &[&str] = &[
+    // 1: resources
+    "CREATE TABLE resources (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        description TEXT,
+        prompt_template TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL
+    );
+    CREATE TABLE resource_images (
+        resource_id TEXT NOT NULL REFERENCES resources(id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        path TEXT NOT NULL,
+        PRIMARY KEY (resource_id, position)
+    );
+    CREATE TABLE meta (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );",
+    // 2: resource version history
+    "CREATE TABLE resource_versions (
+        resource_id TEXT NOT NULL REFERENCES resources(id) ON DELETE CASCADE,
+        version INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        description TEXT,
+        prompt_template TEXT NOT NULL,
+        images TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        PRIMARY KEY (resource_id, version)
+    );",
+    // 3: resource categories, tags and usage counts
+    "ALTER TABLE resources ADD COLUMN category TEXT;
+    ALTER TABLE resources ADD COLUMN usage_count INTEGER NOT NULL DEFAULT 0;
+    CREATE TABLE resource_tags (
+        resource_id TEXT NOT NULL REFERENCES resources(id) ON DELETE CASCADE,
+        tag TEXT NOT NULL,
+        PRIMARY KEY (resource_id, tag)
+    );",
+    // 4: prompt templates are always a JSON array of prompt content items;
+    // plain-text templates become a single text item
+    "UPDATE resources SET prompt_template = '[]' WHERE trim(prompt_template) = '';
+    UPDATE resources
+    SET prompt_template = json_array(json_object('type', 'text', 'value', prompt_template))
+    WHERE CASE WHEN json_valid(prompt_template)
+        THEN json_type(prompt_template) != 'array' ELSE 1 END;
+    UPDATE resource_versions SET prompt_template = '[]' WHERE trim(prompt_template) = '';
+    UPDATE resource_versions
+    SET prompt_template = json_array(json_object('type', 'text', 'value', prompt_template))
+    WHERE CASE WHEN json_valid(prompt_template)
+        THEN json_type(prompt_template) != 'array' ELSE 1 END;",
+];
+
+fn db_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    if !data_dir.exists() {
+        fs::create_dir_all(&data_dir).map_err(|e| e.to_string())?;
+    }
+    Ok(data_dir.join(DB_FILENAME))
+}
+
+/// Owner of the app's single database connection, kept in managed state.
+/// Every access goes through the lock, so resource mutations are serialized.
+pub struct Database(Mutex<Connection>);
+
+impl Database {
+    pub fn lock(&self) -> Result<MutexGuard<'_, Connection>, String> {
+        self.0
+            .lock()
+            .map_err(|_| "Database is unavailable after an earlier failure".to_string())
+    }
+}
+
+fn open(app: &AppHandle) -> Result<Connection, String> {
+    let conn = Connection::open(db_path(app)?).map_err(|e| e.to_string())?;
+    conn.execute_batch("PRAGMA foreign_keys = ON; PRAGMA busy_timeout = 5000;")
+        .map_err(|e| e.to_string())?;
+    Ok(conn)
+}
+
+pub(crate) fn migrate(conn: &mut Connection) -> Result<(), String> {
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .map_err(|e| e.to_string())?;
+    let version: i64 = conn
+        .pragma_query_value(None, "user_version", |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        tx.execute_batch(migration)
+            .map_err(|e| format!("Migration {} failed: {}", i + 1, e))?;
+        tx.pragma_update(None, "user_version", (i + 1) as i64)
+            .map_err(|e| e.to_string())?;
+        tx.commit().map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+pub fn get_meta(conn: &Connection, key: &str) -> Result<Option<String>, String> {
+    conn.query_row("SELECT value FROM meta WHERE key = ?1", [key], |row| {
+        row.get(0)
+    })
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+pub fn set_meta(conn: &Connection, key: &str, value: &str) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO meta (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        params![key, value],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Normalizes a legacy entry's prompt template to the stored JSON form, keeping
+/// anything that does not parse as plain text so it cannot sink the entry.
+fn normalize_legacy_template(entry: &mut serde_json::Value) {
+    let Some(template) = entry.get_mut("promptTemplate") else {
+        return;
+    };
+    let raw = match &mut *template {
+        serde_json::Value::String(raw) => std::mem::take(raw),
+        serde_json::Value::Null => String::new(),
+        other => other.to_string(),
+    };
+    *template = serde_json::Value::String(PromptTemplate::from_stored(raw).to_json());
+}
+
+/// Reads the legacy store's resource entries. A file that cannot be read or
+/// parsed is renamed aside so it is kept for recovery without blocking startup.
+fn read_legacy_entries(json_path: &Path) -> Option<Vec<serde_json::Value>> {
+    let root = fs::read_to_string(json_path)
+        .map_err(|e| e.to_string())
+        .and_then(|content| {
+            serde_json::from_str::<serde_json::Value>(&content).map_err(|e| e.to_string())
+        });
+    match root {
+        Ok(root) => Some(
+            root.get("resources")
+                .and_then(|v| v.as_array())
+                .cloned()
+                .unwrap_or_default(),
+        ),
+        Err(e) => {
+            let backup = json_path.with_extension(format!(
+                "json.unreadable-{}",
+                chrono::Utc::now().timestamp_millis()
+            ));
+            log::error!(
+                "Failed to read legacy resources.json ({}); moving it to {}",
+                e,
+                backup.display()
+            );
+            if let Err(e) = fs::rename(json_path, &backup) {
+                log::error!("Failed to move legacy resources.json aside: {}", e);
+            }
+            None
+        }
+    }
+}
+
+/// One-time import of the legacy `resources.json` store. Entries are parsed
+/// individually so one malformed record cannot take the others with it, and the
+/// JSON file is left in place as a backup.
+fn import_legacy_resources(app: &AppHandle, conn: &mut Connection) -> Result<(), String> {
+    const IMPORTED_KEY: &str = "legacy_resources_imported";
+    if get_meta(conn, IMPORTED_KEY)?.is_some() {
+        return Ok(());
+    }
+
+    let json_path = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?
+        .join("resources.json");
+
+    let mut imported = 0;
+    if json_path.exists() {
+        // Left unmarked; a file that could not be moved aside is retried on the
+        // next start
+        let Some(entries) = read_legacy_entries(&json_path) else {
+            return Ok(());
+        };
+
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        for mut entry in entries {
+            normalize_legacy_template(&mut entry);
+            match serde_json::from_value::<Resource>(entry.clone()) {
+                Ok(resource) => {
+                    if resources::get(&tx, &resource.id)?.is_none() {
+                        resources::insert(&tx, &resource)?;
+                        imported += 1;
+                    }
+                }
+                Err(e) => log::warn!("Skipping malformed legacy resource {}: {}", entry, e),
+            }
+        }
+        set_meta(
+            &tx,
+            IMPORTED_KEY,
+            &chrono::Utc::now().timestamp_millis().to_string(),
+        )?;
+        tx.commit().map_err(|e| e.to_string())?;
+    } else {
+        set_meta(
+            conn,
+            IMPORTED_KEY,
+            &chrono::Utc::now().timestamp_millis().to_string(),
+        )?;
+    }
+
+    if imported > 0 {
+        log::info!("Imported {} resources from resources.json", imported);
+    }
+    Ok(())
+}
+
+/// Creates or upgrades the database and imports legacy data. Called once at
+/// startup; the returned handle is put in managed state.
+pub fn init(app: &AppHandle) -> Result<Database, String> {
+    let mut conn = open(app)?;
+    migrate(&mut conn)?;
+    // Legacy data is best-effort; the app is usable without it
+    if let Err(e) = import_legacy_resources(app, &mut conn) {
+        log::error!("Failed to import legacy resources: {}", e);
+    }
+    Ok(Database(Mutex::new(conn)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PromptContent;
+
+    fn user_version(conn: &Connection) -> i64 {
+        conn.pragma_query_value(None, "user_version", |row| row.get(0))
+            .unwrap()
+    }
+
+    /// An in-memory database migrated up to and including migration `version`.
+    fn database_at(version: usize) -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        for migration in &MIGRATIONS[..version] {
+            conn.execute_batch(migration).unwrap();
+        }
+        conn.pragma_update(None, "user_version", version as i64)
+            .unwrap();
+        conn
+    }
+
+    #[test]
+    fn migrate_creates_the_latest_schema_and_is_idempotent() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn).unwrap();
+        assert_eq!(user_version(&conn), MIGRATIONS.len() as i64);
+        migrate(&mut conn).unwrap();
+        assert_eq!(user_version(&conn), MIGRATIONS.len() as i64);
+
+        set_meta(&conn, "key", "one").unwrap();
+        set_meta(&conn, "key", "two").unwrap();
+        assert_eq!(get_meta(&conn, "key").unwrap().as_deref(), Some("two"));
+        assert_eq!(get_meta(&conn, "missing").unwrap(), None);
+    }
+
+    #[test]
+    fn migration_4_wraps_plain_text_templates() {
+        let mut conn = database_at(3);
+        conn.execute_batch(
+            r#"INSERT INTO resources (id, name, prompt_template, created_at, updated_at)
+               VALUES ('plain', 'Plain', 'a "red" cat', 1, 1),
+                      ('blank', 'Blank', '  ', 1, 1),
+                      ('json', 'Json', '[{"type":"text","value":"kept"}]', 1, 1),
+                      ('object', 'Object', '{"a":1}', 1, 1);
+               INSERT INTO resource_versions
+                   (resource_id, version, name, prompt_template, images, created_at)
+               VALUES ('plain', 1, 'Plain', 'old text', '[]', 1);"#,
+        )
+        .unwrap();
+
+        migrate(&mut conn).unwrap();
+
+        let template = |id: &str| resources::get(&conn, id).unwrap().unwrap().prompt_template;
+        let text = |value: &str| vec![PromptContent::Text(value.to_string())];
+        assert_eq!(template("plain").0, text(r#"a "red" cat"#));
+        assert!(template("blank").0.is_empty());
+        assert_eq!(template("json").0, text("kept"));
+        assert_eq!(template("object").0, text(r#"{"a":1}"#));
+
+        let version = versions::get(&conn, "plain", 1).unwrap().unwrap();
+        assert_eq!(version.prompt_template.0, text("old text"));
+    }
+
+    #[test]
+    fn legacy_entries_with_malformed_templates_still_load() {
+        let mut entry = serde_json::json!({
+            "id": "legacy",
+            "name": "Legacy",
+            "promptTemplate": r#"[{"type":"video","value":"x"}]"#,
+            "images": [],
+            "createdAt": 1,
+            "updatedAt": 1
+        });
+        normalize_legacy_template(&mut entry);
+        let resource: Resource = serde_json::from_value(entry).unwrap();
+        assert_eq!(
+            resource.prompt_template.0,
+            vec![PromptContent::Text(
+                r#"[{"type":"video","value":"x"}]"#.to_string()
+            )]
+        );
+    }
+}
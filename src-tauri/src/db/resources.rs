@@ -0,0 +1,195 @@
+use crate::models::{PromptTemplate, Resource};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+const SELECT_RESOURCE: &str =
+    "SELECT id, name, description, prompt_template, created_at, updated_at,
+     category, usage_count FROM resources";
+
+fn from_row(row: &Row) -> rusqlite::Result<Resource> {
+    Ok(Resource {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        description: row.get(2)?,
+        prompt_template: PromptTemplate::from_stored(row.get(3)?),
+        images: Vec::new(),
+        category: row.get(6)?,
+        tags: Vec::new(),
+        usage_count: row.get(7)?,
+        created_at: row.get(4)?,
+        updated_at: row.get(5)?,
+    })
+}
+
+fn load_images(conn: &Connection, resource: &mut Resource) -> Result<(), String> {
+    let mut stmt = conn
+        .prepare("SELECT path FROM resource_images WHERE resource_id = ?1 ORDER BY position")
+        .map_err(|e| e.to_string())?;
+    resource.images = stmt
+        .query_map([&resource.id], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<Vec<String>>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare("SELECT tag FROM resource_tags WHERE resource_id = ?1 ORDER BY tag")
+        .map_err(|e| e.to_string())?;
+    resource.tags = stmt
+        .query_map([&resource.id], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<Vec<String>>>()
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn write_images(conn: &Connection, resource: &Resource) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM resource_images WHERE resource_id = ?1",
+        [&resource.id],
+    )
+    .map_err(|e| e.to_string())?;
+    for (position, path) in resource.images.iter().enumerate() {
+        conn.execute(
+            "INSERT INTO resource_images (resource_id, position, path) VALUES (?1, ?2, ?3)",
+            params![resource.id, position as i64, path],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    conn.execute(
+        "DELETE FROM resource_tags WHERE resource_id = ?1",
+        [&resource.id],
+    )
+    .map_err(|e| e.to_string())?;
+    for tag in &resource.tags {
+        conn.execute(
+            "INSERT OR IGNORE INTO resource_tags (resource_id, tag) VALUES (?1, ?2)",
+            params![resource.id, tag],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+pub fn list(conn: &Connection) -> Result<Vec<Resource>, String> {
+    let mut stmt = conn
+        .prepare(&format!("{} ORDER BY created_at", SELECT_RESOURCE))
+        .map_err(|e| e.to_string())?;
+    let mut resources = stmt
+        .query_map([], from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<Vec<Resource>>>()
+        .map_err(|e| e.to_string())?;
+    for resource in &mut resources {
+        load_images(conn, resource)?;
+    }
+    Ok(resources)
+}
+
+pub fn get(conn: &Connection, id: &str) -> Result<Option<Resource>, String> {
+    let resource = conn
+        .query_row(
+            &format!("{} WHERE id = ?1", SELECT_RESOURCE),
+            [id],
+            from_row,
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    match resource {
+        Some(mut resource) => {
+            load_images(conn, &mut resource)?;
+            Ok(Some(resource))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Inserts a resource and its images. Call inside a transaction.
+pub fn insert(conn: &Connection, resource: &Resource) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO resources
+         (id, name, description, prompt_template, created_at, updated_at, category, usage_count)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            resource.id,
+            resource.name,
+            resource.description,
+            resource.prompt_template.to_json(),
+            resource.created_at,
+            resource.updated_at,
+            resource.category,
+            resource.usage_count
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    write_images(conn, resource)
+}
+
+/// Replaces all stored fields of an existing resource. Call inside a transaction.
+pub fn update(conn: &Connection, resource: &Resource) -> Result<(), String> {
+    let changed = conn
+        .execute(
+            "UPDATE resources
+             SET name = ?2, description = ?3, prompt_template = ?4, updated_at = ?5,
+                 category = ?6
+             WHERE id = ?1",
+            params![
+                resource.id,
+                resource.name,
+                resource.description,
+                resource.prompt_template.to_json(),
+                resource.updated_at,
+                resource.category
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+    if changed == 0 {
+        return Err("Resource not found".to_string());
+    }
+    write_images(conn, resource)
+}
+
+pub fn delete(conn: &Connection, id: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM resources WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Counts one more use of each resource. Unknown IDs are ignored.
+pub fn increment_usage(conn: &Connection, ids: &[String]) -> Result<(), String> {
+    for id in ids {
+        conn.execute(
+            "UPDATE resources SET usage_count = usage_count + 1 WHERE id = ?1",
+            [id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Distinct tags in use, alphabetically.
+pub fn list_tags(conn: &Connection) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare("SELECT DISTINCT tag FROM resource_tags ORDER BY tag")
+        .map_err(|e| e.to_string())?;
+    let tags = stmt
+        .query_map([], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<Vec<String>>>()
+        .map_err(|e| e.to_string())?;
+    Ok(tags)
+}
+
+/// Distinct categories in use, alphabetically.
+pub fn list_categories(conn: &Connection) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT DISTINCT category FROM resources WHERE category IS NOT NULL ORDER BY category",
+        )
+        .map_err(|e| e.to_string())?;
+    let categories = stmt
+        .query_map([], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<Vec<String>>>()
+        .map_err(|e| e.to_string())?;
+    Ok(categories)
+}
@@ -0,0 +1,79 @@
+use crate::models::{PromptTemplate, ResourceVersion};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+const SELECT_VERSION: &str =
+    "SELECT resource_id, version, name, description, prompt_template, images, created_at
+     FROM resource_versions";
+
+fn from_row(row: &Row) -> rusqlite::Result<ResourceVersion> {
+    let images: String = row.get(5)?;
+    Ok(ResourceVersion {
+        resource_id: row.get(0)?,
+        version: row.get(1)?,
+        name: row.get(2)?,
+        description: row.get(3)?,
+        prompt_template: PromptTemplate::from_stored(row.get(4)?),
+        images: serde_json::from_str(&images).unwrap_or_default(),
+        created_at: row.get(6)?,
+    })
+}
+
+/// Versions of a resource, newest first.
+pub fn list(conn: &Connection, resource_id: &str) -> Result<Vec<ResourceVersion>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "{} WHERE resource_id = ?1 ORDER BY version DESC",
+            SELECT_VERSION
+        ))
+        .map_err(|e| e.to_string())?;
+    let versions = stmt
+        .query_map([resource_id], from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<Vec<ResourceVersion>>>()
+        .map_err(|e| e.to_string())?;
+    Ok(versions)
+}
+
+pub fn get(
+    conn: &Connection,
+    resource_id: &str,
+    version: i64,
+) -> Result<Option<ResourceVersion>, String> {
+    conn.query_row(
+        &format!("{} WHERE resource_id = ?1 AND version = ?2", SELECT_VERSION),
+        params![resource_id, version],
+        from_row,
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+pub fn next_version(conn: &Connection, resource_id: &str) -> Result<i64, String> {
+    conn.query_row(
+        "SELECT COALESCE(MAX(version), 0) + 1 FROM resource_versions WHERE resource_id = ?1",
+        [resource_id],
+        |row| row.get(0),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Call inside the transaction that replaces the resource.
+pub fn insert(conn: &Connection, version: &ResourceVersion) -> Result<(), String> {
+    let images = serde_json::to_string(&version.images).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO resource_versions
+         (resource_id, version, name, description, prompt_template, images, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            version.resource_id,
+            version.version,
+            version.name,
+            version.description,
+            version.prompt_template.to_json(),
+            images,
+            version.created_at
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
@@ -0,0 +1,162 @@
+use ab_glyph::{FontVec, PxScale};
+use image::{DynamicImage, Rgba, RgbaImage};
+use imageproc::drawing::{draw_text_mut, text_size};
+
+const GAP: u32 = 16;
+const CAPTION_SCALE: f32 = 18.0;
+const LINE_HEIGHT: u32 = 24;
+const BACKGROUND: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const CAPTION_COLOR: Rgba<u8> = Rgba([40, 40, 40, 255]);
+const MIN_CELL_SIZE: u32 = 64;
+const MAX_CELL_SIZE: u32 = 1024;
+/// Limits on the finished sheet, keeping its buffer within a few hundred MB.
+const MAX_SHEET_EDGE: u32 = 16384;
+const MAX_SHEET_PIXELS: u64 = 128 * 1024 * 1024;
+
+/// Fonts tried in order; CJK-capable fonts first since prompts are often Chinese.
+const FONT_CANDIDATES: [&str; 10] = [
+    "/System/Library/Fonts/PingFang.ttc",
+    "/System/Library/Fonts/STHeiti Medium.ttc",
+    "/System/Library/Fonts/Hiragino Sans GB.ttc",
+    "C:\\Windows\\Fonts\\msyh.ttc",
+    "C:\\Windows\\Fonts\\simhei.ttf",
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/System/Library/Fonts/Helvetica.ttc",
+    "C:\\Windows\\Fonts\\arial.ttf",
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+];
+
+/// Loads the first available system font for captions.
+pub fn load_caption_font() -> Option<FontVec> {
+    FONT_CANDIDATES.iter().find_map(|path| {
+        let bytes = std::fs::read(path).ok()?;
+        FontVec::try_from_vec_and_index(bytes, 0).ok()
+    })
+}
+
+pub struct Tile {
+    pub image: DynamicImage,
+    pub caption: Vec<String>,
+}
+
+/// Fits `text` into `max_width` pixels, cutting it with an ellipsis if needed.
+fn truncate_to_width(font: &FontVec, text: &str, max_width: u32) -> String {
+    if text_size(PxScale::from(CAPTION_SCALE), font, text).0 <= max_width {
+        return text.to_string();
+    }
+    let mut truncated = String::new();
+    for c in text.chars() {
+        let candidate = format!("{}{}…", truncated, c);
+        if text_size(PxScale::from(CAPTION_SCALE), font, &candidate).0 > max_width {
+            break;
+        }
+        truncated.push(c);
+    }
+    format!("{}…", truncated)
+}
+
+/// Grid geometry of a contact sheet, known before any image is decoded.
+pub struct Layout {
+    columns: u32,
+    /// Edge of the square each image is scaled into.
+    pub cell_size: u32,
+    cell_height: u32,
+    caption_lines: usize,
+    width: u32,
+    height: u32,
+}
+
+impl Layout {
+    /// Grid for `tile_count` tiles with up to `caption_lines` caption lines each.
+    /// `cell_size` is clamped to 64..=1024; a sheet too large to allocate is an
+    /// error.
+    pub fn new(
+        tile_count: usize,
+        columns: u32,
+        cell_size: u32,
+        caption_lines: usize,
+    ) -> Result<Self, String> {
+        let too_large = || "Contact sheet is too large; use fewer images or a smaller cell size";
+        let tile_count = u32::try_from(tile_count).map_err(|_| too_large())?;
+        let cell_size = cell_size.clamp(MIN_CELL_SIZE, MAX_CELL_SIZE);
+        let columns = columns.clamp(1, tile_count.max(1));
+        let rows = tile_count.div_ceil(columns);
+        let caption_height = match u32::try_from(caption_lines).map_err(|_| too_large())? {
+            0 => 0,
+            lines => lines
+                .checked_mul(LINE_HEIGHT)
+                .and_then(|h| h.checked_add(GAP / 2))
+                .ok_or_else(too_large)?,
+        };
+
+        // n cells of `cell` pixels with a gap before, between and after them
+        let span = |n: u32, cell: u32| {
+            n.checked_mul(cell)?
+                .checked_add(n.checked_add(1)?.checked_mul(GAP)?)
+        };
+        let cell_height = cell_size
+            .checked_add(caption_height)
+            .ok_or_else(too_large)?;
+        let width = span(columns, cell_size).ok_or_else(too_large)?;
+        let height = span(rows, cell_height).ok_or_else(too_large)?;
+        if width > MAX_SHEET_EDGE
+            || height > MAX_SHEET_EDGE
+            || width as u64 * height as u64 > MAX_SHEET_PIXELS
+        {
+            return Err(too_large().to_string());
+        }
+
+        Ok(Self {
+            columns,
+            cell_size,
+            cell_height,
+            caption_lines,
+            width,
+            height,
+        })
+    }
+}
+
+/// Draws tiles into `layout`, each scaled into its square with its caption
+/// lines underneath. Captions are skipped when `font` is `None`.
+pub fn compose(tiles: &[Tile], layout: &Layout, font: Option<&FontVec>) -> RgbaImage {
+    let Layout {
+        columns,
+        cell_size,
+        cell_height,
+        caption_lines,
+        width,
+        height,
+    } = *layout;
+    let mut sheet = RgbaImage::from_pixel(width, height, BACKGROUND);
+
+    for (i, tile) in tiles.iter().enumerate() {
+        let col = i as u32 % columns;
+        let row = i as u32 / columns;
+        let cell_x = GAP + col * (cell_size + GAP);
+        let cell_y = GAP + row * (cell_height + GAP);
+
+        let thumb = tile.image.thumbnail(cell_size, cell_size).to_rgba8();
+        let x = cell_x + (cell_size - thumb.width()) / 2;
+        let y = cell_y + (cell_size - thumb.height()) / 2;
+        image::imageops::overlay(&mut sheet, &thumb, x as i64, y as i64);
+
+        if let Some(font) = font {
+            for (line_no, line) in tile.caption.iter().take(caption_lines).enumerate() {
+                let text = truncate_to_width(font, line, cell_size);
+                draw_text_mut(
+                    &mut sheet,
+                    CAPTION_COLOR,
+                    cell_x as i32,
+                    (cell_y + cell_size + GAP / 2 + line_no as u32 * LINE_HEIGHT) as i32,
+                    PxScale::from(CAPTION_SCALE),
+                    font,
+                    &text,
+                );
+            }
+        }
+    }
+
+    sheet
+}
@@ -0,0 +1,179 @@
+pub mod contact_sheet;
+pub mod phash;
+
+use crate::models::{ImageExportOptions, ImageFormat};
+use image::codecs::avif::AvifEncoder;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::imageops::FilterType;
+use image::metadata::Orientation;
+use image::{DynamicImage, ImageDecoder, ImageEncoder, ImageReader};
+use std::borrow::Cow;
+use std::fs;
+use std::io::BufWriter;
+use std::path::Path;
+
+const DEFAULT_JPEG_QUALITY: u8 = 90;
+const DEFAULT_AVIF_QUALITY: u8 = 80;
+const AVIF_SPEED: u8 = 6;
+
+/// A decoded image together with the metadata needed to re-embed it.
+pub struct DecodedImage {
+    pub image: DynamicImage,
+    pub icc_profile: Option<Vec<u8>>,
+    pub exif: Option<Vec<u8>>,
+    pub orientation: Orientation,
+}
+
+pub fn decode(path: &Path) -> Result<DecodedImage, String> {
+    let mut decoder = ImageReader::open(path)
+        .map_err(|e| e.to_string())?
+        .with_guessed_format()
+        .map_err(|e| e.to_string())?
+        .into_decoder()
+        .map_err(|e| e.to_string())?;
+
+    let icc_profile = decoder.icc_profile().unwrap_or(None);
+    let exif = decoder.exif_metadata().unwrap_or(None);
+    let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+    let image = DynamicImage::from_decoder(decoder).map_err(|e| e.to_string())?;
+
+    Ok(DecodedImage {
+        image,
+        icc_profile,
+        exif,
+        orientation,
+    })
+}
+
+/// Fully decodes the file to make sure it is a usable image.
+pub fn validate(path: &Path) -> Result<(), String> {
+    ImageReader::open(path)
+        .map_err(|e| e.to_string())?
+        .with_guessed_format()
+        .map_err(|e| e.to_string())?
+        .decode()
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Downscales so the longest edge is at most `max_edge`, never upscaling.
+pub fn fit_within(image: DynamicImage, max_edge: u32) -> DynamicImage {
+    if image.width().max(image.height()) <= max_edge {
+        return image;
+    }
+    image.resize(max_edge, max_edge, FilterType::Lanczos3)
+}
+
+/// Returns whether the EXIF block, and with it the orientation tag, was embedded.
+fn attach_metadata(encoder: &mut impl ImageEncoder, decoded: &DecodedImage) -> bool {
+    // Not every encoder can embed every kind of metadata; skip what it rejects.
+    if let Some(icc) = &decoded.icc_profile {
+        let _ = encoder.set_icc_profile(icc.clone());
+    }
+    match &decoded.exif {
+        Some(exif) => encoder.set_exif_metadata(exif.clone()).is_ok(),
+        None => false,
+    }
+}
+
+/// The pixels to encode: as decoded when the output keeps a usable EXIF
+/// orientation tag, otherwise with the orientation baked in.
+fn oriented(decoded: &DecodedImage, orientation_tag_kept: bool) -> Cow<'_, DynamicImage> {
+    if orientation_tag_kept || decoded.orientation == Orientation::NoTransforms {
+        return Cow::Borrowed(&decoded.image);
+    }
+    let mut image = decoded.image.clone();
+    image.apply_orientation(decoded.orientation);
+    Cow::Owned(image)
+}
+
+/// Rejects option combinations the encoders cannot honour.
+pub fn validate_export_options(options: &ImageExportOptions) -> Result<(), String> {
+    if options.max_edge == Some(0) {
+        return Err("Maximum edge must be greater than 0".to_string());
+    }
+    if options.format == Some(ImageFormat::Webp) && options.quality.is_some() {
+        return Err("WebP is exported losslessly and takes no quality setting".to_string());
+    }
+    Ok(())
+}
+
+pub fn encode_to_file(
+    decoded: &DecodedImage,
+    format: ImageFormat,
+    quality: Option<u8>,
+    keep_metadata: bool,
+    dest: &Path,
+) -> Result<(), String> {
+    let file = fs::File::create(dest).map_err(|e| e.to_string())?;
+    let writer = BufWriter::new(file);
+
+    let result = match format {
+        ImageFormat::Png => {
+            let mut encoder = PngEncoder::new(writer);
+            let exif_kept = keep_metadata && attach_metadata(&mut encoder, decoded);
+            oriented(decoded, exif_kept).write_with_encoder(encoder)
+        }
+        ImageFormat::Jpeg => {
+            let quality = quality.unwrap_or(DEFAULT_JPEG_QUALITY).clamp(1, 100);
+            let mut encoder = JpegEncoder::new_with_quality(writer, quality);
+            let exif_kept = keep_metadata && attach_metadata(&mut encoder, decoded);
+            // JPEG has no alpha channel
+            DynamicImage::ImageRgb8(oriented(decoded, exif_kept).to_rgb8())
+                .write_with_encoder(encoder)
+        }
+        ImageFormat::Webp => {
+            let mut encoder = WebPEncoder::new_lossless(writer);
+            let exif_kept = keep_metadata && attach_metadata(&mut encoder, decoded);
+            oriented(decoded, exif_kept).write_with_encoder(encoder)
+        }
+        ImageFormat::Avif => {
+            let quality = quality.unwrap_or(DEFAULT_AVIF_QUALITY).clamp(1, 100);
+            let mut encoder = AvifEncoder::new_with_speed_quality(writer, AVIF_SPEED, quality);
+            if keep_metadata {
+                attach_metadata(&mut encoder, decoded);
+            }
+            // AVIF readers ignore the EXIF orientation tag, so always bake it in
+            oriented(decoded, false).write_with_encoder(encoder)
+        }
+    };
+
+    result.map_err(|e| e.to_string())
+}
+
+/// Writes `source` to `dest` applying the export options. Files that need no
+/// conversion are copied byte-for-byte.
+pub fn export_image(
+    source: &Path,
+    dest: &Path,
+    options: &ImageExportOptions,
+) -> Result<(), String> {
+    validate_export_options(options)?;
+    let source_format = source
+        .extension()
+        .and_then(|e| e.to_str())
+        .and_then(ImageFormat::from_extension);
+    let max_edge = options.max_edge.or(options.preset.map(|p| p.max_edge()));
+    let keep_metadata = options.keep_metadata.unwrap_or(true);
+
+    // Sources in formats we cannot encode (e.g. GIF) can still be copied as-is
+    let same_format = options.format.is_none() || options.format == source_format;
+    if same_format && max_edge.is_none() && keep_metadata && options.quality.is_none() {
+        fs::copy(source, dest).map_err(|e| e.to_string())?;
+        return Ok(());
+    }
+
+    let format = options
+        .format
+        .or(source_format)
+        .ok_or("Unsupported source image format")?;
+
+    let mut decoded = decode(source)?;
+    if let Some(max_edge) = max_edge {
+        decoded.image = fit_within(decoded.image, max_edge);
+    }
+
+    encode_to_file(&decoded, format, options.quality, keep_metadata, dest)
+}
@@ -0,0 +1,138 @@
+//! 64-bit perceptual hashes. Visually similar images have hashes with a small
+//! Hamming distance.
+
+use image::imageops::FilterType;
+use image::DynamicImage;
+use std::f64::consts::PI;
+
+fn grayscale_pixels(image: &DynamicImage, width: u32, height: u32) -> Vec<f64> {
+    image
+        .resize_exact(width, height, FilterType::Triangle)
+        .to_luma8()
+        .pixels()
+        .map(|p| p.0[0] as f64)
+        .collect()
+}
+
+fn bits_above(values: &[f64], threshold: f64) -> u64 {
+    values.iter().enumerate().fold(
+        0u64,
+        |hash, (i, &v)| {
+            if v > threshold {
+                hash | (1 << i)
+            } else {
+                hash
+            }
+        },
+    )
+}
+
+/// aHash: 8x8 grayscale thumbnail thresholded at its mean.
+pub fn average_hash(image: &DynamicImage) -> u64 {
+    let pixels = grayscale_pixels(image, 8, 8);
+    let mean = pixels.iter().sum::<f64>() / pixels.len() as f64;
+    bits_above(&pixels, mean)
+}
+
+/// dHash: compares horizontally adjacent pixels of a 9x8 thumbnail.
+pub fn difference_hash(image: &DynamicImage) -> u64 {
+    let pixels = grayscale_pixels(image, 9, 8);
+    let mut hash = 0u64;
+    for row in 0..8 {
+        for col in 0..8 {
+            if pixels[row * 9 + col] < pixels[row * 9 + col + 1] {
+                hash |= 1 << (row * 8 + col);
+            }
+        }
+    }
+    hash
+}
+
+/// pHash: low-frequency 8x8 block of the DCT of a 32x32 thumbnail,
+/// thresholded at its median with the DC term excluded.
+pub fn perceptual_hash(image: &DynamicImage) -> u64 {
+    const SIZE: usize = 32;
+    const LOW: usize = 8;
+
+    let pixels = grayscale_pixels(image, SIZE as u32, SIZE as u32);
+    let cosines: Vec<f64> = (0..LOW)
+        .flat_map(|u| {
+            (0..SIZE).map(move |x| (((2 * x + 1) as f64 * u as f64 * PI) / (2 * SIZE) as f64).cos())
+        })
+        .collect();
+
+    let mut coefficients = Vec::with_capacity(LOW * LOW);
+    for v in 0..LOW {
+        for u in 0..LOW {
+            let mut sum = 0.0;
+            for y in 0..SIZE {
+                for x in 0..SIZE {
+                    sum += pixels[y * SIZE + x] * cosines[u * SIZE + x] * cosines[v * SIZE + y];
+                }
+            }
+            coefficients.push(sum);
+        }
+    }
+
+    let mut ac: Vec<f64> = coefficients[1..].to_vec();
+    ac.sort_by(|a, b| a.total_cmp(b));
+    let median = ac[ac.len() / 2];
+
+    let mut hash = bits_above(&coefficients, median);
+    // The DC term only reflects overall brightness
+    hash &= !1;
+    hash
+}
+
+pub fn hamming_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    /// A 6x6 grid of blocks with varied gray levels, so every hash has
+    /// clear structure to pick up at any size.
+    fn blocks(width: u32, height: u32, flip: bool) -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, |x, y| {
+            let (bx, by) = (x * 6 / width, y * 6 / height);
+            let v = ((bx * 37 + by * 91 + bx * by * 13) % 256) as u8;
+            let v = if flip { 255 - v } else { v };
+            Rgb([v, v, v])
+        }))
+    }
+
+    #[test]
+    fn hamming_distance_counts_differing_bits() {
+        assert_eq!(hamming_distance(0, 0), 0);
+        assert_eq!(hamming_distance(0b1011, 0b0001), 2);
+        assert_eq!(hamming_distance(0, u64::MAX), 64);
+        assert_eq!(hamming_distance(u64::MAX, 1 << 63), 63);
+    }
+
+    #[test]
+    fn hamming_distance_is_symmetric() {
+        let (a, b) = (0x0123_4567_89ab_cdef, 0xfedc_ba98_7654_3210);
+        assert_eq!(hamming_distance(a, b), hamming_distance(b, a));
+    }
+
+    #[test]
+    fn hashes_survive_rescaling() {
+        let small = blocks(64, 48, false);
+        let large = blocks(640, 480, false);
+        for hash in [average_hash, difference_hash, perceptual_hash] {
+            assert!(hamming_distance(hash(&small), hash(&large)) <= 4);
+        }
+    }
+
+    #[test]
+    fn hashes_separate_inverted_images() {
+        let image = blocks(64, 64, false);
+        let inverted = blocks(64, 64, true);
+        for hash in [average_hash, difference_hash, perceptual_hash] {
+            assert!(hamming_distance(hash(&image), hash(&inverted)) > 16);
+        }
+    }
+}
@@ -1,5 +1,13 @@
 pub mod commands;
+pub mod db;
+pub mod imaging;
 pub mod models;
+pub mod prompt;
+pub mod search;
+#[cfg(test)]
+mod test_support;
+
+use tauri::Manager;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -17,6 +25,16 @@ pub fn run() {
                         .build(),
                 )?;
             }
+
+            app.manage(db::init(app.handle())?);
+
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = commands::trash::purge_expired(&handle) {
+                    log::warn!("Failed to purge expired trash items: {}", e);
+                }
+            });
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -29,7 +47,53 @@ pub fn run() {
             commands::resources::delete_resource,
             commands::gallery::list_gallery_images,
             commands::gallery::open_image_in_viewer,
-            commands::gallery::download_image
+            commands::gallery::download_image,
+            commands::gallery::delete_image,
+            commands::metadata::get_image_metadata,
+            commands::metadata::set_image_rating,
+            commands::metadata::toggle_image_favorite,
+            commands::metadata::add_image_tags,
+            commands::metadata::remove_image_tags,
+            commands::metadata::list_image_tags,
+            commands::metadata::query_gallery_images,
+            commands::albums::create_album,
+            commands::albums::list_albums,
+            commands::albums::update_album,
+            commands::albums::delete_album,
+            commands::albums::add_images_to_album,
+            commands::albums::remove_images_from_album,
+            commands::albums::reorder_album,
+            commands::albums::set_album_cover,
+            commands::albums::list_album_images,
+            commands::trash::list_trash,
+            commands::trash::restore_trash_item,
+            commands::trash::empty_trash,
+            commands::export::export_images_zip,
+            commands::duplicates::get_image_hashes,
+            commands::duplicates::find_duplicate_images,
+            commands::duplicates::find_similar_images,
+            commands::duplicates::find_similar,
+            commands::search::search_gallery_images,
+            commands::contact_sheet::create_contact_sheet,
+            commands::bundles::export_resource_bundle,
+            commands::bundles::import_resource_bundle,
+            commands::versions::list_resource_versions,
+            commands::versions::diff_resource_versions,
+            commands::versions::restore_resource_version,
+            commands::resources::list_resource_tags,
+            commands::resources::list_resource_categories,
+            commands::search::search_resources,
+            commands::resources::duplicate_resource,
+            commands::resources::create_resource_from_images,
+            commands::integrity::check_resource_integrity,
+            commands::integrity::repair_resource_integrity,
+            commands::resources::render_resource_prompt,
+            commands::prompt::render_prompt,
+            commands::wildcards::list_wildcards,
+            commands::wildcards::expand_prompt,
+            commands::wildcards::queue_wildcard_generations,
+            commands::batch::run_batch_file,
+            commands::compare::compare_providers
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -11,6 +11,10 @@ pub struct GeneratePayload {
     pub count: u32,
     pub reference_images: Option<Vec<String>>,
     pub image_mapping: Option<HashMap<String, String>>,
+    /// Resources referenced by the prompt, recorded for search.
+    pub resource_ids: Option<Vec<String>>,
+    /// Values for `{{name}}` placeholders in the prompt.
+    pub variables: Option<HashMap<String, String>>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -21,14 +25,39 @@ pub struct GeneratedImage {
     pub created_at: u64,
 }
 
+/// One piece of a prompt template, serialized as `{"type": ..., "value": ...}`
+/// to match the frontend's `PromptContent`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", content = "value", rename_all = "kebab-case")]
+pub enum PromptContent {
+    Text(String),
+    /// Path of an image belonging to the resource.
+    ImageReference(String),
+    /// ID of another resource.
+    ResourceReference(String),
+}
+
+/// A resource's prompt template. Crosses the IPC boundary and is stored as a
+/// JSON string; see `crate::prompt` for parsing and rendering.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(try_from = "String", into = "String")]
+pub struct PromptTemplate(pub Vec<PromptContent>);
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct Resource {
     pub id: String,
     pub name: String,
     pub description: Option<String>,
-    pub prompt_template: String,
+    pub prompt_template: PromptTemplate,
     pub images: Vec<String>,
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Number of generations that referenced this resource.
+    #[serde(default)]
+    pub usage_count: i64,
     pub created_at: i64,
     pub updated_at: i64,
 }
@@ -40,6 +69,23 @@ pub struct CreateResourcePayload {
     pub description: Option<String>,
     pub prompt: String,
     pub image_paths: Vec<String>,
+    pub category: Option<String>,
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageImportFailure {
+    pub path: String,
+    pub reason: String,
+}
+
+/// A saved resource along with any images that could not be imported into it.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceImportResult {
+    pub resource: Resource,
+    pub failed_images: Vec<ImageImportFailure>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -50,4 +96,523 @@ pub struct UpdateResourcePayload {
     pub description: Option<String>,
     pub prompt: Option<String>,
     pub images: Option<Vec<String>>,
+    /// An empty string clears the category.
+    pub category: Option<String>,
+    pub tags: Option<Vec<String>>,
+    /// `updated_at` of the version the edit was based on. When set, the update is
+    /// rejected if the resource has changed since.
+    pub expected_updated_at: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageMetadata {
+    pub hash: String,
+    pub rating: u8,
+    pub favorite: bool,
+    pub tags: Vec<String>,
+    pub updated_at: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageQuery {
+    pub favorite: Option<bool>,
+    pub min_rating: Option<u8>,
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ResourceSort {
+    Name,
+    Created,
+    Updated,
+    Usage,
+}
+
+/// Filters for `search_resources`. Without a sort, text matches are ordered by
+/// relevance and everything else by creation time.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceQuery {
+    pub text: Option<String>,
+    /// Resources must carry all of these tags.
+    pub tags: Option<Vec<String>>,
+    pub category: Option<String>,
+    pub sort: Option<ResourceSort>,
+    /// Reverses the chosen sort; ignored without one.
+    pub descending: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Album {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    /// Content hashes of the member images, in display order.
+    pub images: Vec<String>,
+    pub cover: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateAlbumPayload {
+    pub id: String,
+    pub name: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum TrashItemKind {
+    Image,
+    Resource,
+    /// A folder no resource owned, restored as-is.
+    Folder,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashItem {
+    pub id: String,
+    pub kind: TrashItemKind,
+    pub name: String,
+    pub original_path: String,
+    pub trashed_path: String,
+    /// Snapshot of the resource record, present for `Resource` items.
+    pub resource: Option<Resource>,
+    /// The resource's version history, reinserted on restore. Its images
+    /// travel with the folder under `.versions`.
+    #[serde(default)]
+    pub versions: Vec<ResourceVersion>,
+    pub deleted_at: i64,
+}
+
+/// Parameters an output image was generated with, keyed by its content hash.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerationRecord {
+    pub prompt: String,
+    pub negative_prompt: Option<String>,
+    pub provider: String,
+    pub model: String,
+    pub width: u32,
+    pub height: u32,
+    pub reference_images: Vec<String>,
+    #[serde(default)]
+    pub resource_ids: Vec<String>,
+    pub created_at: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportZipPayload {
+    /// Explicit gallery paths; takes precedence over `album_id` and `query`.
+    pub paths: Option<Vec<String>>,
+    pub album_id: Option<String>,
+    pub query: Option<ImageQuery>,
+    pub default_filename: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportSummary {
+    pub archive_path: String,
+    pub exported: usize,
+    pub skipped: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportProgress {
+    pub current: usize,
+    pub total: usize,
+    pub filename: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    Webp,
+    Avif,
+}
+
+impl ImageFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Jpeg => "jpg",
+            ImageFormat::Webp => "webp",
+            ImageFormat::Avif => "avif",
+        }
+    }
+
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext.to_lowercase().as_str() {
+            "png" => Some(ImageFormat::Png),
+            "jpg" | "jpeg" => Some(ImageFormat::Jpeg),
+            "webp" => Some(ImageFormat::Webp),
+            "avif" => Some(ImageFormat::Avif),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ResizePreset {
+    /// 1080px long edge, for social media posts.
+    Social,
+    /// 1920px long edge.
+    FullHd,
+    /// 512px long edge.
+    Thumbnail,
+}
+
+impl ResizePreset {
+    pub fn max_edge(&self) -> u32 {
+        match self {
+            ResizePreset::Social => 1080,
+            ResizePreset::FullHd => 1920,
+            ResizePreset::Thumbnail => 512,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageExportOptions {
+    /// Target format; defaults to the source format.
+    pub format: Option<ImageFormat>,
+    /// Encoder quality 1-100 for JPEG and AVIF. WebP is always lossless and
+    /// rejects a quality.
+    pub quality: Option<u8>,
+    /// Longest edge in pixels, at least 1; takes precedence over `preset`.
+    pub max_edge: Option<u32>,
+    pub preset: Option<ResizePreset>,
+    /// Keep ICC profile and EXIF data. Defaults to true.
+    pub keep_metadata: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    Ahash,
+    Dhash,
+    #[default]
+    Phash,
+}
+
+/// Content and perceptual hashes of an image. Perceptual hashes are 64-bit
+/// values encoded as 16 hex digits so they survive the trip through JSON.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageHashes {
+    pub content_hash: String,
+    pub ahash: String,
+    pub dhash: String,
+    pub phash: String,
+}
+
+impl ImageHashes {
+    pub fn perceptual(&self, algorithm: HashAlgorithm) -> u64 {
+        let hex = match algorithm {
+            HashAlgorithm::Ahash => &self.ahash,
+            HashAlgorithm::Dhash => &self.dhash,
+            HashAlgorithm::Phash => &self.phash,
+        };
+        u64::from_str_radix(hex, 16).unwrap_or_default()
+    }
+
+    /// Whether every perceptual hash is well-formed hex, so a damaged cache
+    /// entry is recomputed rather than read as 0.
+    pub fn is_valid(&self) -> bool {
+        [&self.ahash, &self.dhash, &self.phash]
+            .iter()
+            .all(|hex| hex.len() == 16 && u64::from_str_radix(hex, 16).is_ok())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateGroup {
+    pub images: Vec<GeneratedImage>,
+    /// Largest hash distance that linked two members; 0 for exact duplicates.
+    pub max_distance: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum ImageSource {
+    Gallery,
+    Resource,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SimilarImage {
+    pub path: String,
+    pub filename: String,
+    pub source: ImageSource,
+    pub resource_id: Option<String>,
+    /// Combined pHash + dHash Hamming distance, out of 128.
+    pub distance: u32,
+    /// 1.0 for identical hashes, 0.0 for maximally different ones.
+    pub similarity: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum CaptionField {
+    Filename,
+    Prompt,
+    Provider,
+    Size,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ContactSheetPayload {
+    pub paths: Vec<String>,
+    /// Defaults to a roughly square grid.
+    pub columns: Option<u32>,
+    /// Longest edge of each tile in pixels.
+    pub cell_size: Option<u32>,
+    /// Caption lines under each tile, in order. No captions when empty.
+    pub captions: Option<Vec<CaptionField>>,
+}
+
+/// How to handle bundle resources whose ID already exists locally.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictStrategy {
+    /// Import as a new resource with a fresh ID.
+    #[default]
+    Duplicate,
+    /// Leave the local resource untouched.
+    Skip,
+    /// Overwrite the local resource, moving the old one to the trash.
+    Replace,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleImportReport {
+    pub imported: Vec<Resource>,
+    /// Names of resources skipped because of a conflict.
+    pub skipped: Vec<String>,
+    pub failed_images: Vec<ImageImportFailure>,
+    /// Resource IDs dropped from imported templates because they are neither in
+    /// the bundle nor in the local library.
+    pub dropped_references: Vec<String>,
+}
+
+/// Snapshot of a resource taken before an update replaced it.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceVersion {
+    pub resource_id: String,
+    pub version: i64,
+    pub name: String,
+    pub description: Option<String>,
+    pub prompt_template: PromptTemplate,
+    /// Copies kept in the resource's `.versions` folder.
+    pub images: Vec<String>,
+    /// `updated_at` of the resource when this version was current.
+    pub created_at: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum DiffKind {
+    Unchanged,
+    Added,
+    Removed,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffLine {
+    pub kind: DiffKind,
+    pub text: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum IntegrityIssueKind {
+    /// A listed image file does not exist.
+    MissingImage,
+    /// A listed image exists but cannot be decoded.
+    UnreadableImage,
+    /// The prompt template references an image the resource does not have.
+    DanglingTemplateReference,
+    /// A folder under `Oneiria/Resources` that belongs to no resource.
+    OrphanFolder,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityIssue {
+    pub kind: IntegrityIssueKind,
+    pub resource_id: Option<String>,
+    pub resource_name: Option<String>,
+    pub path: String,
+    pub detail: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityReport {
+    pub issues: Vec<IntegrityIssue>,
+    /// Whether the issues were fixed, as opposed to only reported.
+    pub repaired: bool,
+}
+
+/// What a repair does with resource folders that belong to no resource.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum OrphanFolderAction {
+    /// Leave the folder alone.
+    Keep,
+    /// Create a resource from the images in the folder.
+    Adopt,
+    /// Move the folder to the trash.
+    Delete,
+}
+
+/// A studio reference image that top-level `image-reference` items point at by ID.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptImage {
+    pub id: String,
+    pub path: String,
+    pub display_name: String,
+    /// Set for images the studio took from a resource.
+    pub resource_id: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RenderPromptPayload {
+    pub content: Vec<PromptContent>,
+    #[serde(default)]
+    pub images: Vec<PromptImage>,
+}
+
+/// An image sent along with a prompt, under the name the prompt uses for it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptReference {
+    pub name: String,
+    pub path: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RenderedPrompt {
+    pub prompt: String,
+    /// Referenced images in first-use order, each path once.
+    pub references: Vec<PromptReference>,
+    /// Every resource expanded into the prompt, including nested ones.
+    pub resource_ids: Vec<String>,
+    /// Placeholders left in the prompt, to be filled when generating.
+    pub variables: Vec<PromptVariable>,
+}
+
+/// A `{{name}}` or `{{name:default}}` placeholder in a prompt.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptVariable {
+    pub name: String,
+    pub default: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum ExpansionMode {
+    /// Draw each wildcard at random.
+    #[default]
+    Random,
+    /// Every combination of wildcard options.
+    Cartesian,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpandPromptPayload {
+    pub prompt: String,
+    pub mode: Option<ExpansionMode>,
+    /// Prompts to draw in random mode (default 1); a cap in cartesian mode.
+    pub count: Option<usize>,
+    /// Random when omitted; the seed used is returned.
+    pub seed: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptExpansion {
+    pub prompts: Vec<String>,
+    pub seed: u64,
+}
+
+/// Generates once per expansion of `payload.prompt`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WildcardBatchPayload {
+    pub payload: GeneratePayload,
+    pub mode: Option<ExpansionMode>,
+    pub count: Option<usize>,
+    pub seed: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedGenerations {
+    pub batch_id: String,
+    pub prompts: Vec<String>,
+    pub seed: u64,
+}
+
+/// Emitted as `generation-progress` after each queued generation finishes.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerationProgress {
+    pub batch_id: String,
+    pub current: usize,
+    pub total: usize,
+    pub prompt: String,
+    pub paths: Vec<String>,
+    pub error: Option<String>,
+}
+
+/// A batch file run started in the background; results go to `folder`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchRun {
+    pub batch_id: String,
+    pub folder: String,
+    pub rows: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderResult {
+    pub provider: String,
+    pub paths: Vec<String>,
+    pub error: Option<String>,
+    pub duration_ms: i64,
+}
+
+/// Results of one prompt run against several providers, in request order.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderComparison {
+    pub folder: String,
+    pub results: Vec<ProviderResult>,
 }
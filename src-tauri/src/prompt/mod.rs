@@ -0,0 +1,243 @@
+//! Resource prompt templates: parsing, validation and rendering.
+//!
+//! Templates are stored as a JSON array of `PromptContent` items. Older
+//! resources saved plain text instead, which parses as a single text item.
+
+pub mod render;
+pub mod variables;
+pub mod wildcards;
+
+use crate::models::{PromptContent, PromptTemplate};
+use std::collections::HashMap;
+use std::path::Path;
+
+impl PromptTemplate {
+    /// Parses a stored or submitted template. Blank input is an empty template
+    /// and anything that is not a JSON array is plain text; a JSON array with
+    /// malformed items is rejected.
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        if raw.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        match serde_json::from_str::<serde_json::Value>(raw) {
+            Ok(value @ serde_json::Value::Array(_)) => serde_json::from_value(value)
+                .map(Self)
+                .map_err(|e| format!("Invalid prompt template: {}", e)),
+            _ => Ok(Self(vec![PromptContent::Text(raw.to_string())])),
+        }
+    }
+
+    /// Like `parse`, but keeps unparseable stored data as plain text rather than
+    /// failing the read.
+    pub fn from_stored(raw: String) -> Self {
+        Self::parse(&raw).unwrap_or(Self(vec![PromptContent::Text(raw)]))
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&self.0).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    /// Rejects references with an empty value.
+    pub fn validate(&self) -> Result<(), String> {
+        for item in &self.0 {
+            match item {
+                PromptContent::ImageReference(v) if v.trim().is_empty() => {
+                    return Err("Prompt template has an empty image reference".to_string())
+                }
+                PromptContent::ResourceReference(v) if v.trim().is_empty() => {
+                    return Err("Prompt template has an empty resource reference".to_string())
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    pub fn image_refs(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().filter_map(|item| match item {
+            PromptContent::ImageReference(path) => Some(path.as_str()),
+            _ => None,
+        })
+    }
+
+    pub fn resource_refs(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().filter_map(|item| match item {
+            PromptContent::ResourceReference(id) => Some(id.as_str()),
+            _ => None,
+        })
+    }
+
+    /// The text items alone, for searching.
+    pub fn plain_text(&self) -> String {
+        self.0
+            .iter()
+            .filter_map(|item| match item {
+                PromptContent::Text(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Points image references at new paths; unmapped ones are kept.
+    pub fn remap_images(&self, mapping: &HashMap<String, String>) -> Self {
+        Self(
+            self.0
+                .iter()
+                .map(|item| match item {
+                    PromptContent::ImageReference(path) => {
+                        PromptContent::ImageReference(mapping.get(path).unwrap_or(path).clone())
+                    }
+                    other => other.clone(),
+                })
+                .collect(),
+        )
+    }
+
+    /// Points resource references at new IDs; unmapped ones are kept.
+    pub fn remap_resources(&self, mapping: &HashMap<String, String>) -> Self {
+        Self(
+            self.0
+                .iter()
+                .map(|item| match item {
+                    PromptContent::ResourceReference(id) => {
+                        PromptContent::ResourceReference(mapping.get(id).unwrap_or(id).clone())
+                    }
+                    other => other.clone(),
+                })
+                .collect(),
+        )
+    }
+
+    /// Drops image references for which `keep` returns false.
+    pub fn retain_images(&mut self, keep: impl Fn(&str) -> bool) {
+        self.0.retain(|item| match item {
+            PromptContent::ImageReference(path) => keep(path),
+            _ => true,
+        });
+    }
+
+    /// Drops resource references for which `keep` returns false.
+    pub fn retain_resources(&mut self, keep: impl Fn(&str) -> bool) {
+        self.0.retain(|item| match item {
+            PromptContent::ResourceReference(id) => keep(id),
+            _ => true,
+        });
+    }
+}
+
+/// `r_{first 6 chars of resource ID}_{file stem}`, as `getPrefixedName` builds it
+/// for resource images.
+pub fn resource_image_name(resource_id: &str, path: &str) -> String {
+    let short_id: String = resource_id.chars().take(6).collect();
+    let filename = Path::new(path)
+        .file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "image".to_string());
+    // Strip the last extension only, keeping dotfiles whole
+    let display_name = match filename.rfind('.') {
+        Some(i) if i > 0 => filename[..i].to_string(),
+        _ => filename,
+    };
+    format!("r_{}_{}", short_id, display_name)
+}
+
+impl TryFrom<String> for PromptTemplate {
+    type Error = String;
+
+    fn try_from(raw: String) -> Result<Self, Self::Error> {
+        Self::parse(&raw)
+    }
+}
+
+impl From<PromptTemplate> for String {
+    fn from(template: PromptTemplate) -> Self {
+        template.to_json()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(value: &str) -> PromptContent {
+        PromptContent::Text(value.to_string())
+    }
+
+    #[test]
+    fn parse_treats_blank_input_as_empty() {
+        assert_eq!(
+            PromptTemplate::parse("").unwrap(),
+            PromptTemplate::default()
+        );
+        assert_eq!(
+            PromptTemplate::parse("  \n").unwrap(),
+            PromptTemplate::default()
+        );
+    }
+
+    #[test]
+    fn parse_reads_legacy_plain_text_as_one_text_item() {
+        assert_eq!(
+            PromptTemplate::parse("a red cat").unwrap().0,
+            vec![text("a red cat")]
+        );
+        // JSON that is not an array is still just text
+        assert_eq!(
+            PromptTemplate::parse(r#"{"a": 1}"#).unwrap().0,
+            vec![text(r#"{"a": 1}"#)]
+        );
+    }
+
+    #[test]
+    fn parse_reads_json_items() {
+        let raw = r#"[{"type":"text","value":"a "},{"type":"image-reference","value":"/x.png"},{"type":"resource-reference","value":"abc"}]"#;
+        assert_eq!(
+            PromptTemplate::parse(raw).unwrap().0,
+            vec![
+                text("a "),
+                PromptContent::ImageReference("/x.png".to_string()),
+                PromptContent::ResourceReference("abc".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_rejects_malformed_items_but_from_stored_keeps_them_as_text() {
+        let raw = r#"[{"type":"video","value":"x"}]"#;
+        assert!(PromptTemplate::parse(raw).is_err());
+        assert_eq!(
+            PromptTemplate::from_stored(raw.to_string()).0,
+            vec![text(raw)]
+        );
+    }
+
+    #[test]
+    fn legacy_text_round_trips_through_storage() {
+        let template = PromptTemplate::parse("line one\nline \"two\"").unwrap();
+        let stored: String = template.clone().into();
+        assert_eq!(PromptTemplate::try_from(stored).unwrap(), template);
+    }
+
+    #[test]
+    fn serde_round_trips_as_a_json_string() {
+        let template = PromptTemplate(vec![
+            text("hi "),
+            PromptContent::ResourceReference("abc".to_string()),
+        ]);
+        let json = serde_json::to_string(&template).unwrap();
+        assert!(json.starts_with('"'));
+        assert_eq!(
+            serde_json::from_str::<PromptTemplate>(&json).unwrap(),
+            template
+        );
+    }
+
+    #[test]
+    fn resource_image_name_strips_only_the_last_extension() {
+        assert_eq!(
+            resource_image_name("1234567890", "/res/face.final.png"),
+            "r_123456_face.final"
+        );
+        assert_eq!(resource_image_name("ab", "/res/.hidden"), "r_ab_.hidden");
+    }
+}
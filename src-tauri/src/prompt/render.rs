@@ -0,0 +1,205 @@
+//! Expands prompt content into the final prompt text sent to a provider.
+
+use super::{resource_image_name, variables};
+use crate::db;
+use crate::models::{PromptContent, PromptImage, PromptReference, RenderedPrompt};
+use rusqlite::Connection;
+
+/// `s_{first 4 chars of image ID}_{display name}`, or `r_{first 6 chars of
+/// resource ID}_{display name}` for images taken from a resource, as
+/// `getPrefixedName` builds them.
+fn studio_image_name(image: &PromptImage) -> String {
+    match &image.resource_id {
+        Some(resource_id) => {
+            let short_id: String = resource_id.chars().take(6).collect();
+            format!("r_{}_{}", short_id, image.display_name)
+        }
+        None => {
+            let short_id: String = image.id.chars().take(4).collect();
+            format!("s_{}_{}", short_id, image.display_name)
+        }
+    }
+}
+
+struct Renderer<'a> {
+    conn: &'a Connection,
+    studio_images: &'a [PromptImage],
+    output: RenderedPrompt,
+    /// Resources currently being expanded, outermost first.
+    stack: Vec<String>,
+}
+
+impl Renderer<'_> {
+    fn add_reference(&mut self, name: String, path: &str) {
+        if !self.output.references.iter().any(|r| r.path == path) {
+            self.output.references.push(PromptReference {
+                name,
+                path: path.to_string(),
+            });
+        }
+    }
+
+    fn expand_resource(&mut self, id: &str) -> Result<(), String> {
+        let Some(resource) = db::resources::get(self.conn, id)? else {
+            log::warn!("Resource {} not found, skipping", id);
+            return Ok(());
+        };
+        if self.stack.iter().any(|open| open == id) {
+            let mut names = Vec::new();
+            for open in &self.stack {
+                let name = db::resources::get(self.conn, open)?.map(|r| r.name);
+                names.push(name.unwrap_or_else(|| open.clone()));
+            }
+            names.push(resource.name);
+            return Err(format!("Resource reference cycle: {}", names.join(" → ")));
+        }
+
+        if !self.output.resource_ids.iter().any(|r| r == id) {
+            self.output.resource_ids.push(id.to_string());
+        }
+        // All of a resource's images go along, not only those its template names
+        for image in &resource.images {
+            self.add_reference(resource_image_name(id, image), image);
+        }
+
+        self.stack.push(id.to_string());
+        for item in &resource.prompt_template.0 {
+            match item {
+                PromptContent::Text(text) => self.output.prompt.push_str(text),
+                PromptContent::ImageReference(path) => {
+                    let name = resource_image_name(id, path);
+                    self.output.prompt.push_str(&format!("图片文件[{}]", name));
+                    self.add_reference(name, path);
+                }
+                PromptContent::ResourceReference(nested) => self.expand_resource(nested)?,
+            }
+        }
+        self.stack.pop();
+        Ok(())
+    }
+
+    fn render_item(&mut self, item: &PromptContent) -> Result<(), String> {
+        match item {
+            PromptContent::Text(text) => self.output.prompt.push_str(text),
+            PromptContent::ImageReference(image_id) => {
+                let Some(image) = self.studio_images.iter().find(|i| &i.id == image_id) else {
+                    log::warn!("Reference image {} not found, skipping", image_id);
+                    return Ok(());
+                };
+                let name = studio_image_name(image);
+                self.output.prompt.push_str(&format!("图片文件[{}]", name));
+                self.add_reference(name, &image.path);
+            }
+            PromptContent::ResourceReference(id) => self.expand_resource(id)?,
+        }
+        Ok(())
+    }
+}
+
+/// Renders studio prompt content: text is kept, studio images become
+/// `图片文件[name]`, and resource references are replaced by their templates,
+/// recursively. `{{name}}` placeholders are kept for `generate_image` to fill
+/// and listed in the result. Fails on a resource that (indirectly) references itself.
+/// Missing images and resources are skipped.
+pub fn render(
+    conn: &Connection,
+    content: &[PromptContent],
+    studio_images: &[PromptImage],
+) -> Result<RenderedPrompt, String> {
+    let mut renderer = Renderer {
+        conn,
+        studio_images,
+        output: RenderedPrompt {
+            prompt: String::new(),
+            references: Vec::new(),
+            resource_ids: Vec::new(),
+            variables: Vec::new(),
+        },
+        stack: Vec::new(),
+    };
+    for item in content {
+        renderer.render_item(item)?;
+    }
+    renderer.output.variables = variables::variables(&renderer.output.prompt);
+    Ok(renderer.output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{PromptTemplate, Resource};
+
+    fn database() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        db::migrate(&mut conn).unwrap();
+        conn
+    }
+
+    fn add_resource(conn: &Connection, id: &str, template: Vec<PromptContent>, images: &[&str]) {
+        let resource = Resource {
+            id: id.to_string(),
+            name: id.to_uppercase(),
+            description: None,
+            prompt_template: PromptTemplate(template),
+            images: images.iter().map(|s| s.to_string()).collect(),
+            category: None,
+            tags: Vec::new(),
+            usage_count: 0,
+            created_at: 0,
+            updated_at: 0,
+        };
+        db::resources::insert(conn, &resource).unwrap();
+    }
+
+    fn text(s: &str) -> PromptContent {
+        PromptContent::Text(s.to_string())
+    }
+
+    fn resource(id: &str) -> PromptContent {
+        PromptContent::ResourceReference(id.to_string())
+    }
+
+    #[test]
+    fn nested_resources_expand_in_place() {
+        let conn = database();
+        add_resource(&conn, "a", vec![text("a ["), resource("b"), text("]")], &[]);
+        add_resource(&conn, "b", vec![text("b")], &["/res/b/face.png"]);
+
+        let rendered = render(&conn, &[text("> "), resource("a")], &[]).unwrap();
+        assert_eq!(rendered.prompt, "> a [b]");
+        assert_eq!(rendered.resource_ids, vec!["a", "b"]);
+        assert_eq!(rendered.references.len(), 1);
+        assert_eq!(rendered.references[0].name, "r_b_face");
+    }
+
+    #[test]
+    fn reference_cycles_are_rejected() {
+        let conn = database();
+        add_resource(&conn, "a", vec![resource("b")], &[]);
+        add_resource(&conn, "b", vec![resource("a")], &[]);
+
+        assert_eq!(
+            render(&conn, &[resource("a")], &[]).unwrap_err(),
+            "Resource reference cycle: A → B → A"
+        );
+    }
+
+    #[test]
+    fn shared_images_are_referenced_once() {
+        let conn = database();
+        let shared = "/res/shared/face.png";
+        add_resource(
+            &conn,
+            "a",
+            vec![PromptContent::ImageReference(shared.to_string())],
+            &[shared],
+        );
+        add_resource(&conn, "b", vec![resource("a")], &[shared]);
+
+        let rendered = render(&conn, &[resource("a"), resource("b")], &[]).unwrap();
+        assert_eq!(rendered.prompt, "图片文件[r_a_face]图片文件[r_a_face]");
+        assert_eq!(rendered.references.len(), 1);
+        assert_eq!(rendered.references[0].path, shared);
+        assert_eq!(rendered.resource_ids, vec!["a", "b"]);
+    }
+}
@@ -0,0 +1,139 @@
+//! `{{name}}` and `{{name:default}}` placeholders in prompt text.
+
+use crate::models::PromptVariable;
+use std::collections::HashMap;
+
+/// A placeholder found in text: byte range of the whole `{{...}}` plus its
+/// trimmed name and optional default.
+struct Placeholder<'a> {
+    start: usize,
+    end: usize,
+    name: &'a str,
+    default: Option<&'a str>,
+}
+
+fn placeholders(text: &str) -> Vec<Placeholder<'_>> {
+    let mut found = Vec::new();
+    let mut pos = 0;
+    while let Some(open) = text[pos..].find("{{").map(|i| pos + i) {
+        let Some(close) = text[open + 2..].find("}}").map(|i| open + 2 + i) else {
+            break;
+        };
+        let inner = &text[open + 2..close];
+        let (name, default) = match inner.split_once(':') {
+            Some((name, default)) => (name.trim(), Some(default.trim())),
+            None => (inner.trim(), None),
+        };
+        // `{{}}` and names spanning another `{{` are left as literal text
+        if name.is_empty() || inner.contains("{{") {
+            pos = open + 2;
+            continue;
+        }
+        found.push(Placeholder {
+            start: open,
+            end: close + 2,
+            name,
+            default,
+        });
+        pos = close + 2;
+    }
+    found
+}
+
+/// Placeholders in `text`, each name once, in order of first appearance. The
+/// first default given for a name wins.
+pub fn variables(text: &str) -> Vec<PromptVariable> {
+    let mut vars: Vec<PromptVariable> = Vec::new();
+    for p in placeholders(text) {
+        match vars.iter_mut().find(|v| v.name == p.name) {
+            Some(var) => {
+                if var.default.is_none() {
+                    var.default = p.default.map(str::to_string);
+                }
+            }
+            None => vars.push(PromptVariable {
+                name: p.name.to_string(),
+                default: p.default.map(str::to_string),
+            }),
+        }
+    }
+    vars
+}
+
+/// Replaces every placeholder with its value from `values`, falling back to the
+/// placeholder's default (or another occurrence's). A variable with neither is
+/// left as written, so literal `{{...}}` text survives.
+pub fn substitute(text: &str, values: &HashMap<String, String>) -> String {
+    let defaults = variables(text);
+    let mut output = String::with_capacity(text.len());
+    let mut last = 0;
+    for p in placeholders(text) {
+        let value = values
+            .get(p.name)
+            .map(String::as_str)
+            .or(p.default)
+            .or_else(|| {
+                defaults
+                    .iter()
+                    .find(|v| v.name == p.name)
+                    .and_then(|v| v.default.as_deref())
+            });
+        let Some(value) = value else {
+            log::warn!("No value for prompt variable '{}'; left as is", p.name);
+            continue;
+        };
+        output.push_str(&text[last..p.start]);
+        output.push_str(value);
+        last = p.end;
+    }
+    output.push_str(&text[last..]);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn variables_lists_each_name_once_with_the_first_default() {
+        let vars = variables("{{a}} {{ b : red }} {{a:x}} {{b:blue}}");
+        let found: Vec<(&str, Option<&str>)> = vars
+            .iter()
+            .map(|v| (v.name.as_str(), v.default.as_deref()))
+            .collect();
+        assert_eq!(found, vec![("a", Some("x")), ("b", Some("red"))]);
+    }
+
+    #[test]
+    fn substitute_prefers_values_then_defaults() {
+        let text = "a {{size:big}} {{animal}} and a {{size}} {{animal}}";
+        assert_eq!(
+            substitute(text, &values(&[("animal", "cat")])),
+            "a big cat and a big cat"
+        );
+        assert_eq!(
+            substitute(text, &values(&[("animal", "dog"), ("size", "tiny")])),
+            "a tiny dog and a tiny dog"
+        );
+    }
+
+    #[test]
+    fn substitute_leaves_unresolved_placeholders_as_written() {
+        assert_eq!(
+            substitute("keep {{this}} and {{}} and {{open", &HashMap::new()),
+            "keep {{this}} and {{}} and {{open"
+        );
+    }
+
+    #[test]
+    fn substitute_allows_empty_defaults() {
+        assert_eq!(substitute("a{{x:}}b", &HashMap::new()), "ab");
+    }
+}
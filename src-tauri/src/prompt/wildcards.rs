@@ -0,0 +1,285 @@
+//! Wildcard expansion: `{red|blue|green}` picks one option and `__name__` one
+//! line of `Wildcards/name.txt`. Options are not expanded again, and `{{...}}`
+//! variable placeholders pass through untouched. A `__name__` without a usable
+//! wildcard file is kept as written.
+
+use crate::models::ExpansionMode;
+use std::fs;
+use std::path::Path;
+
+/// Upper bound on the prompts one expansion may produce.
+pub const MAX_EXPANSIONS: usize = 1000;
+
+enum Segment {
+    Literal(String),
+    Choice(Vec<String>),
+}
+
+/// Wildcard names are bare file stems, so a name can never leave the
+/// wildcards folder.
+fn is_wildcard_name_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-')
+}
+
+/// Options in a wildcard file: one per line, blank lines and `#` comments
+/// skipped. `None` if the file is missing, unreadable or has no options.
+fn load_wildcard(dir: &Path, name: &str) -> Option<Vec<String>> {
+    let path = dir.join(format!("{}.txt", name));
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) => {
+            log::warn!("Wildcard '{}' left as is: {}", name, e);
+            return None;
+        }
+    };
+    let options: Vec<String> = content
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(str::to_string)
+        .collect();
+    if options.is_empty() {
+        log::warn!("Wildcard '{}' left as is: the file has no options", name);
+        return None;
+    }
+    Some(options)
+}
+
+fn parse(prompt: &str, wildcard_dir: &Path) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut literal = String::new();
+    let mut rest = prompt;
+
+    while let Some(c) = rest.chars().next() {
+        if rest.starts_with("{{") {
+            // Variable placeholder: copy through to its closing braces
+            let end = rest.find("}}").map(|i| i + 2).unwrap_or(rest.len());
+            literal.push_str(&rest[..end]);
+            rest = &rest[end..];
+            continue;
+        }
+        if c == '{' {
+            if let Some(close) = rest.find('}') {
+                let inner = &rest[1..close];
+                if inner.contains('|') && !inner.contains('{') {
+                    segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    segments.push(Segment::Choice(
+                        inner.split('|').map(|o| o.trim().to_string()).collect(),
+                    ));
+                    rest = &rest[close + 1..];
+                    continue;
+                }
+            }
+        }
+        if let Some(after) = rest.strip_prefix("__") {
+            if let Some(len) = after.find("__") {
+                let name = &after[..len];
+                let options = (!name.is_empty() && name.chars().all(is_wildcard_name_char))
+                    .then(|| load_wildcard(wildcard_dir, name))
+                    .flatten();
+                if let Some(options) = options {
+                    segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    segments.push(Segment::Choice(options));
+                    rest = &after[len + 2..];
+                    continue;
+                }
+            }
+        }
+        literal.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    segments.push(Segment::Literal(literal));
+    segments
+}
+
+/// SplitMix64, so a seed gives the same expansions on every platform.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+fn join(segments: &[Segment], picks: &[usize]) -> String {
+    let mut picks = picks.iter();
+    segments
+        .iter()
+        .map(|segment| match segment {
+            Segment::Literal(text) => text.as_str(),
+            Segment::Choice(options) => options[*picks.next().unwrap_or(&0)].as_str(),
+        })
+        .collect()
+}
+
+/// Expands `prompt` into concrete prompts. `Random` draws `count` prompts using
+/// `seed`; `Cartesian` lists every combination in order, stopping after `count`
+/// if given. Either way at least one and at most `MAX_EXPANSIONS` are produced.
+pub fn expand(
+    prompt: &str,
+    wildcard_dir: &Path,
+    mode: ExpansionMode,
+    count: Option<usize>,
+    seed: u64,
+) -> Result<Vec<String>, String> {
+    if count == Some(0) {
+        return Err("Count must be at least 1".to_string());
+    }
+    let segments = parse(prompt, wildcard_dir);
+    let sizes: Vec<usize> = segments
+        .iter()
+        .filter_map(|s| match s {
+            Segment::Choice(options) => Some(options.len()),
+            Segment::Literal(_) => None,
+        })
+        .collect();
+
+    match mode {
+        ExpansionMode::Random => {
+            let count = count.unwrap_or(1);
+            if count > MAX_EXPANSIONS {
+                return Err(format!("At most {} prompts per expansion", MAX_EXPANSIONS));
+            }
+            let mut rng = Rng(seed);
+            Ok((0..count)
+                .map(|_| {
+                    let picks: Vec<usize> = sizes.iter().map(|&n| rng.below(n)).collect();
+                    join(&segments, &picks)
+                })
+                .collect())
+        }
+        ExpansionMode::Cartesian => {
+            let total = sizes
+                .iter()
+                .try_fold(1usize, |acc, &n| acc.checked_mul(n))
+                .unwrap_or(usize::MAX);
+            let count = count.unwrap_or(total).min(total);
+            if count > MAX_EXPANSIONS {
+                return Err(format!(
+                    "{} combinations exceed the limit of {}; set a count",
+                    total, MAX_EXPANSIONS
+                ));
+            }
+            // Odometer over the choice indices, last choice varying fastest
+            let mut picks = vec![0usize; sizes.len()];
+            let mut prompts = Vec::with_capacity(count);
+            for _ in 0..count {
+                prompts.push(join(&segments, &picks));
+                for i in (0..picks.len()).rev() {
+                    picks[i] += 1;
+                    if picks[i] < sizes[i] {
+                        break;
+                    }
+                    picks[i] = 0;
+                }
+            }
+            Ok(prompts)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    /// A fresh wildcards folder holding the given `name.txt` files.
+    fn wildcard_dir(test: &str, files: &[(&str, &str)]) -> TempDir {
+        let dir = TempDir::new(&format!("wildcards-{}", test));
+        for (name, content) in files {
+            fs::write(dir.join(format!("{}.txt", name)), content).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn cartesian_lists_every_combination_in_order() {
+        let dir = wildcard_dir("cartesian", &[("animal", "cat\n# comment\n\ndog\n")]);
+        let prompts = expand(
+            "a {red|blue} __animal__",
+            &dir,
+            ExpansionMode::Cartesian,
+            None,
+            0,
+        );
+        assert_eq!(
+            prompts.unwrap(),
+            vec!["a red cat", "a red dog", "a blue cat", "a blue dog"]
+        );
+        assert_eq!(
+            expand("{a|b|c}", &dir, ExpansionMode::Cartesian, Some(2), 0).unwrap(),
+            vec!["a", "b"]
+        );
+    }
+
+    #[test]
+    fn random_is_reproducible_for_a_seed() {
+        let dir = wildcard_dir("random", &[("color", "red\ngreen\nblue\n")]);
+        let prompt = "{big|small} __color__ {cat|dog|bird}";
+        let first = expand(prompt, &dir, ExpansionMode::Random, Some(20), 42).unwrap();
+        let second = expand(prompt, &dir, ExpansionMode::Random, Some(20), 42).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(first.len(), 20);
+        assert_ne!(
+            first,
+            expand(prompt, &dir, ExpansionMode::Random, Some(20), 43).unwrap()
+        );
+    }
+
+    #[test]
+    fn unknown_and_unsafe_wildcards_stay_literal() {
+        let dir = wildcard_dir("literal", &[("empty", "# nothing\n")]);
+        let prompt = "__missing__ __empty__ __../secret__ __/etc/passwd__";
+        assert_eq!(
+            expand(prompt, &dir, ExpansionMode::Cartesian, None, 0).unwrap(),
+            vec![prompt]
+        );
+    }
+
+    #[test]
+    fn variables_and_plain_braces_pass_through() {
+        let dir = wildcard_dir("passthrough", &[]);
+        assert_eq!(
+            expand(
+                "{{size:a|b}} {x} {a|b}",
+                &dir,
+                ExpansionMode::Cartesian,
+                None,
+                0
+            )
+            .unwrap(),
+            vec!["{{size:a|b}} {x} a", "{{size:a|b}} {x} b"]
+        );
+    }
+
+    #[test]
+    fn expansions_are_capped() {
+        let dir = wildcard_dir("capped", &[]);
+        let prompt = "{a|b|c|d|e|f|g|h|i|j}".repeat(3) + "{a|b}";
+        assert!(expand(&prompt, &dir, ExpansionMode::Cartesian, None, 0).is_err());
+        assert_eq!(
+            expand(&prompt, &dir, ExpansionMode::Cartesian, Some(5), 0)
+                .unwrap()
+                .len(),
+            5
+        );
+        assert!(expand(
+            "x",
+            &dir,
+            ExpansionMode::Random,
+            Some(MAX_EXPANSIONS + 1),
+            0
+        )
+        .is_err());
+        assert!(expand("x", &dir, ExpansionMode::Random, Some(0), 0).is_err());
+        assert!(expand("x", &dir, ExpansionMode::Cartesian, Some(0), 0).is_err());
+    }
+}
@@ -0,0 +1,234 @@
+//! Ranked full-text matching for mixed Chinese/English text.
+//!
+//! Latin text is split into lowercase words. CJK text has no word boundaries,
+//! so it is indexed as overlapping character unigrams and bigrams and queried
+//! by bigram, which matches multi-character words without a dictionary.
+
+use std::collections::HashMap;
+
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}'   // Hiragana, Katakana
+        | '\u{3400}'..='\u{4DBF}' // CJK Extension A
+        | '\u{4E00}'..='\u{9FFF}' // CJK Unified Ideographs
+        | '\u{AC00}'..='\u{D7AF}' // Hangul syllables
+        | '\u{F900}'..='\u{FAFF}' // CJK Compatibility Ideographs
+    )
+}
+
+fn push_cjk_run(run: &[char], for_query: bool, tokens: &mut Vec<String>) {
+    if run.len() == 1 {
+        tokens.push(run[0].to_string());
+        return;
+    }
+    if !for_query {
+        tokens.extend(run.iter().map(|c| c.to_string()));
+    }
+    tokens.extend(run.windows(2).map(|w| w.iter().collect::<String>()));
+}
+
+/// Splits text into search terms. Documents get CJK unigrams and bigrams,
+/// queries only bigrams (or a unigram for a lone character).
+pub fn tokenize(text: &str, for_query: bool) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let mut cjk_run: Vec<char> = Vec::new();
+
+    for c in text.chars() {
+        if is_cjk(c) {
+            if !word.is_empty() {
+                tokens.push(std::mem::take(&mut word));
+            }
+            cjk_run.push(c);
+        } else {
+            if !cjk_run.is_empty() {
+                push_cjk_run(&cjk_run, for_query, &mut tokens);
+                cjk_run.clear();
+            }
+            if c.is_alphanumeric() {
+                word.extend(c.to_lowercase());
+            } else if !word.is_empty() {
+                tokens.push(std::mem::take(&mut word));
+            }
+        }
+    }
+    if !word.is_empty() {
+        tokens.push(word);
+    }
+    if !cjk_run.is_empty() {
+        push_cjk_run(&cjk_run, for_query, &mut tokens);
+    }
+    tokens
+}
+
+/// A searchable field of a document. Every document must list its fields in
+/// the same order so per-field length statistics line up.
+pub struct Field<'a> {
+    pub text: &'a str,
+    pub weight: f64,
+}
+
+struct IndexedField {
+    term_freqs: HashMap<String, u32>,
+    len: usize,
+    weight: f64,
+}
+
+fn index_field(field: &Field) -> IndexedField {
+    let tokens = tokenize(field.text, false);
+    let mut term_freqs = HashMap::new();
+    for token in &tokens {
+        *term_freqs.entry(token.clone()).or_insert(0) += 1;
+    }
+    IndexedField {
+        term_freqs,
+        len: tokens.len(),
+        weight: field.weight,
+    }
+}
+
+/// Scores documents against `query` with field-weighted BM25. Only documents
+/// containing every query term are returned, best match first.
+pub fn rank(documents: &[Vec<Field>], query: &str) -> Vec<(usize, f64)> {
+    let mut terms = tokenize(query, true);
+    terms.sort();
+    terms.dedup();
+    if terms.is_empty() || documents.is_empty() {
+        return Vec::new();
+    }
+
+    let indexed: Vec<Vec<IndexedField>> = documents
+        .iter()
+        .map(|fields| fields.iter().map(index_field).collect())
+        .collect();
+
+    let field_count = indexed.iter().map(|d| d.len()).max().unwrap_or(0);
+    let avg_len: Vec<f64> = (0..field_count)
+        .map(|f| {
+            let total: usize = indexed
+                .iter()
+                .filter_map(|d| d.get(f))
+                .map(|field| field.len)
+                .sum();
+            (total as f64 / indexed.len() as f64).max(1.0)
+        })
+        .collect();
+
+    let n = indexed.len() as f64;
+    let idf: HashMap<&String, f64> = terms
+        .iter()
+        .map(|term| {
+            let df = indexed
+                .iter()
+                .filter(|d| d.iter().any(|f| f.term_freqs.contains_key(term)))
+                .count() as f64;
+            (term, (1.0 + (n - df + 0.5) / (df + 0.5)).ln())
+        })
+        .collect();
+
+    let mut results = Vec::new();
+    'documents: for (i, doc) in indexed.iter().enumerate() {
+        let mut score = 0.0;
+        for term in &terms {
+            let mut term_score = 0.0;
+            for (f, field) in doc.iter().enumerate() {
+                let tf = *field.term_freqs.get(term).unwrap_or(&0) as f64;
+                if tf == 0.0 {
+                    continue;
+                }
+                let norm = 1.0 - B + B * field.len as f64 / avg_len[f];
+                term_score += field.weight * idf[term] * tf * (K1 + 1.0) / (tf + K1 * norm);
+            }
+            if term_score == 0.0 {
+                continue 'documents;
+            }
+            score += term_score;
+        }
+        results.push((i, score));
+    }
+
+    results.sort_by(|a, b| b.1.total_cmp(&a.1));
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc(text: &str) -> Vec<Field<'_>> {
+        vec![Field { text, weight: 1.0 }]
+    }
+
+    #[test]
+    fn tokenize_lowercases_latin_words_and_drops_punctuation() {
+        assert_eq!(
+            tokenize("Red-haired Girl, 2 cats!", false),
+            vec!["red", "haired", "girl", "2", "cats"]
+        );
+    }
+
+    #[test]
+    fn tokenize_indexes_cjk_as_unigrams_and_bigrams() {
+        assert_eq!(
+            tokenize("红色头发", false),
+            vec!["红", "色", "头", "发", "红色", "色头", "头发"]
+        );
+    }
+
+    #[test]
+    fn tokenize_queries_cjk_by_bigram() {
+        assert_eq!(tokenize("红色头发", true), vec!["红色", "色头", "头发"]);
+        assert_eq!(tokenize("猫", true), vec!["猫"]);
+    }
+
+    #[test]
+    fn tokenize_splits_mixed_scripts() {
+        assert_eq!(
+            tokenize("一只cat在草地上。", true),
+            vec!["一只", "cat", "在草", "草地", "地上"]
+        );
+    }
+
+    #[test]
+    fn rank_requires_every_query_term() {
+        let docs = vec![doc("red cat"), doc("blue cat"), doc("red dog")];
+        let results = rank(&docs, "red cat");
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0, 0);
+    }
+
+    #[test]
+    fn rank_orders_by_term_frequency_and_field_weight() {
+        let titled = |title, body| {
+            vec![
+                Field {
+                    text: title,
+                    weight: 3.0,
+                },
+                Field {
+                    text: body,
+                    weight: 1.0,
+                },
+            ]
+        };
+        let docs = vec![
+            titled("", "cat on a mat with a hat"),
+            titled("", "cat cat cat"),
+            titled("cat", ""),
+        ];
+        let order: Vec<usize> = rank(&docs, "cat").into_iter().map(|(i, _)| i).collect();
+        assert_eq!(order, vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn rank_matches_cjk_words_inside_longer_text() {
+        let docs = vec![doc("一个红色头发的女孩"), doc("红色的花")];
+        let results = rank(&docs, "头发");
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0, 0);
+        assert!(rank(&docs, "").is_empty());
+    }
+}
@@ -1,5 +1,9 @@
 import { useEffect, useState } from 'react';
-import { useResourceStore, type Resource } from '../../stores/resourceStore';
+import {
+  useResourceStore,
+  type ImageImportFailure,
+  type Resource,
+} from '../../stores/resourceStore';
 import { useGenerationStore } from '../../stores/generationStore';
 import { ResourceList } from './components/ResourceList';
 import { ResourceEditor } from './components/ResourceEditor';
@@ -31,8 +35,9 @@ export const ResourceLibrary = () => {
     imagePaths: string[];
   }) => {
     try {
+      let failedImages: ImageImportFailure[];
       if (editingResource) {
-        await updateResource({
+        failedImages = await updateResource({
           id: editingResource.id,
           name: data.name,
           description: data.description,
@@ -41,9 +46,13 @@ export const ResourceLibrary = () => {
         });
         toast.success(t('library.toast.updated'));
       } else {
-        await createResource(data);
+        failedImages = await createResource(data);
         toast.success(t('library.toast.created'));
       }
+      failedImages.forEach((failure) => {
+        const filename = failure.path.split(/[\\/]/).pop() || failure.path;
+        toast.warning(t('library.toast.imageSkipped', { filename, reason: failure.reason }));
+      });
       setIsEditorOpen(false);
       setEditingResource(null);
     } catch (e) {
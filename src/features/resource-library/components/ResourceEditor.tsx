@@ -190,6 +190,9 @@ export const ResourceEditor = ({ initialData, isOpen, onClose, onSave }: Props)
     description: description,
     promptTemplate: JSON.stringify(promptContent),
     images: referenceImages.map((img) => img.originalPath),
+    category: initialData?.category,
+    tags: initialData?.tags ?? [],
+    usageCount: initialData?.usageCount ?? 0,
     createdAt: Date.now(),
     updatedAt: Date.now(),
   };
@@ -1,11 +1,9 @@
 import { create } from 'zustand';
 import { invoke } from '@tauri-apps/api/core';
 import { exists } from '@tauri-apps/plugin-fs';
-import { useResourceStore, type Resource } from './resourceStore';
+import type { Resource } from './resourceStore';
 import type { PromptContent } from '../types/prompt';
 import { useReferenceImageStore } from './referenceImageStore';
-import type { ReferenceImage } from '../types/referenceImage';
-import { getPrefixedName } from '../lib/imageUtils';
 
 interface GenerationState {
   prompt: string;
@@ -14,6 +12,8 @@ interface GenerationState {
   width: number;
   height: number;
   count: number;
+  /** Values for `{{name}}` placeholders in the prompt and its resources. */
+  variables: Record<string, string>;
   isGenerating: boolean;
   generatedImages: string[];
   setPrompt: (prompt: string) => void;
@@ -21,11 +21,17 @@ interface GenerationState {
   setNegativePrompt: (prompt: string) => void;
   setSize: (width: number, height: number) => void;
   setCount: (count: number) => void;
+  setVariable: (name: string, value: string) => void;
   loadResource: (resource: Resource) => void;
   generate: () => Promise<void>;
-  getSerializedPrompt: () => string;
-  getReferencedImagePaths: () => string[];
-  getImageMapping: () => Record<string, string>;
+}
+
+/** Output of the backend `render_prompt` command. */
+interface RenderedPrompt {
+  prompt: string;
+  references: { name: string; path: string }[];
+  resourceIds: string[];
+  variables: { name: string; default?: string }[];
 }
 
 export const useGenerationStore = create<GenerationState>((set, get) => ({
@@ -35,6 +41,7 @@ export const useGenerationStore = create<GenerationState>((set, get) => ({
   width: 1024,
   height: 1024,
   count: 1,
+  variables: {},
   isGenerating: false,
   generatedImages: [],
   setPrompt: (prompt) => set({ prompt }),
@@ -42,145 +49,41 @@ export const useGenerationStore = create<GenerationState>((set, get) => ({
   setNegativePrompt: (prompt) => set({ negativePrompt: prompt }),
   setSize: (width, height) => set({ width, height }),
   setCount: (count) => set({ count }),
+  setVariable: (name, value) =>
+    set((state) => ({ variables: { ...state.variables, [name]: value } })),
   loadResource: (resource) =>
     set((state) => ({
       // Append a resource-reference tag to existing content (same as @ mention)
       promptContent: [...state.promptContent, { type: 'resource-reference', value: resource.id }],
     })),
-  getSerializedPrompt: () => {
-    const { prompt, promptContent } = get();
-    // If promptContent is empty but prompt is set (legacy/text-only), return prompt
-    if (promptContent.length === 0) return prompt;
-
-    return promptContent
-      .map((item) => {
-        if (item.type === 'text') return item.value;
-        if (item.type === 'image-reference') {
-          const img = useReferenceImageStore.getState().getImageById(item.value);
-          return img ? `图片文件[${getPrefixedName(img)}]` : '';
-        }
-        if (item.type === 'resource-reference') {
-          const res = useResourceStore.getState().getResourceById(item.value);
-          if (!res) {
-            console.warn(`Warning: Resource ${item.value} not found, skipping`);
-            return '';
-          }
-
-          try {
-            const parsed = JSON.parse(res.promptTemplate);
-            if (Array.isArray(parsed)) {
-              return (parsed as PromptContent[])
-                .map((c) => {
-                  if (c.type === 'text') return c.value;
-                  if (c.type === 'image-reference') {
-                    const path = c.value;
-                    const filename = path.split(/[\\/]/).pop() || 'image';
-                    const displayName =
-                      filename.substring(0, filename.lastIndexOf('.')) || filename;
-
-                    const img = {
-                      id: path,
-                      displayName: displayName,
-                      source: 'resource' as const,
-                      resourceId: res.id,
-                    } as ReferenceImage;
-                    return `图片文件[${getPrefixedName(img)}]`;
-                  }
-                  return '';
-                })
-                .join('');
-            }
-          } catch {
-            return res.promptTemplate;
-          }
-        }
-        return '';
-      })
-      .join('');
-  },
-  getReferencedImagePaths: () => {
-    const { promptContent } = get();
-    const paths = new Set<string>();
-
-    promptContent.forEach((item) => {
-      if (item.type === 'image-reference') {
-        const img = useReferenceImageStore.getState().getImageById(item.value);
-        if (img) paths.add(img.originalPath);
-      } else if (item.type === 'resource-reference') {
-        const res = useResourceStore.getState().getResourceById(item.value);
-        if (res) {
-          res.images.forEach((path) => paths.add(path));
-        } else {
-          console.warn(`Warning: Resource ${item.value} not found, skipping`);
-        }
-      }
-    });
-
-    return Array.from(paths);
-  },
-  getImageMapping: () => {
-    const { promptContent } = get();
-    const mapping: Record<string, string> = {};
-
-    promptContent.forEach((item) => {
-      if (item.type === 'image-reference') {
-        const img = useReferenceImageStore.getState().getImageById(item.value);
-        if (img) {
-          mapping[getPrefixedName(img)] = img.originalPath;
-        }
-      } else if (item.type === 'resource-reference') {
-        const res = useResourceStore.getState().getResourceById(item.value);
-        if (res) {
-          try {
-            const parsed = JSON.parse(res.promptTemplate);
-            if (Array.isArray(parsed)) {
-              (parsed as PromptContent[]).forEach((c) => {
-                if (c.type === 'image-reference') {
-                  const path = c.value;
-                  const filename = path.split(/[\\/]/).pop() || 'image';
-                  const displayName = filename.substring(0, filename.lastIndexOf('.')) || filename;
-                  const img = {
-                    id: path,
-                    displayName,
-                    source: 'resource' as const,
-                    resourceId: res.id,
-                  } as ReferenceImage;
-                  mapping[getPrefixedName(img)] = path;
-                }
-              });
-            }
-          } catch {
-            // Legacy/plain text
-          }
-        }
-      }
-    });
-
-    return mapping;
-  },
   generate: async () => {
-    const {
-      negativePrompt,
-      width,
-      height,
-      count,
-      getSerializedPrompt,
-      getReferencedImagePaths,
-      getImageMapping,
-    } = get();
-    const prompt = getSerializedPrompt();
-
-    if (!prompt) return;
+    const { prompt, promptContent, negativePrompt, width, height, count, variables } = get();
+    // Legacy/text-only prompts have no structured content
+    const content: PromptContent[] =
+      promptContent.length > 0 ? promptContent : [{ type: 'text', value: prompt }];
 
     set({ isGenerating: true });
     try {
-      const referenceImages = getReferencedImagePaths();
-      const imageMapping = getImageMapping();
+      // Prompt serialization and resource expansion happen in the backend so every
+      // caller produces the same prompt
+      const studioImages = useReferenceImageStore.getState().images.map((img) => ({
+        id: img.id,
+        path: img.originalPath,
+        displayName: img.displayName,
+        resourceId: img.source === 'resource' ? (img.resourceId ?? 'unknown') : null,
+      }));
+      const rendered = await invoke<RenderedPrompt>('render_prompt', {
+        payload: { content, images: studioImages },
+      });
+      if (!rendered.prompt) {
+        set({ isGenerating: false });
+        return;
+      }
 
       const validImages: string[] = [];
       const validMapping: Record<string, string> = {};
 
-      for (const [name, path] of Object.entries(imageMapping)) {
+      for (const { name, path } of rendered.references) {
         try {
           if (await exists(path)) {
             validMapping[name] = path;
@@ -193,27 +96,17 @@ export const useGenerationStore = create<GenerationState>((set, get) => ({
         }
       }
 
-      for (const path of referenceImages) {
-        if (!validImages.includes(path)) {
-          try {
-            if (await exists(path)) {
-              validImages.push(path);
-            }
-          } catch (err) {
-            console.warn(`Failed to validate reference image path: ${path}`, err);
-          }
-        }
-      }
-
       const images = await invoke<string[]>('generate_image', {
         payload: {
-          prompt,
+          prompt: rendered.prompt,
           negativePrompt: negativePrompt || null,
           width,
           height,
           count,
           referenceImages: validImages.length > 0 ? validImages : null,
           imageMapping: Object.keys(validMapping).length > 0 ? validMapping : null,
+          resourceIds: rendered.resourceIds.length > 0 ? rendered.resourceIds : null,
+          variables: rendered.variables.length > 0 ? variables : null,
         },
       });
       set({ generatedImages: images, isGenerating: false });
@@ -7,10 +7,23 @@ export interface Resource {
   description?: string;
   promptTemplate: string;
   images: string[];
+  category?: string;
+  tags: string[];
+  usageCount: number;
   createdAt: number;
   updatedAt: number;
 }
 
+export interface ImageImportFailure {
+  path: string;
+  reason: string;
+}
+
+interface ResourceImportResult {
+  resource: Resource;
+  failedImages: ImageImportFailure[];
+}
+
 interface ResourceState {
   resources: Resource[];
   isLoading: boolean;
@@ -20,14 +33,20 @@ interface ResourceState {
     description?: string;
     prompt: string;
     imagePaths: string[];
-  }) => Promise<void>;
+    category?: string;
+    tags?: string[];
+  }) => Promise<ImageImportFailure[]>;
   updateResource: (payload: {
     id: string;
     name?: string;
     description?: string;
     prompt?: string;
     images?: string[];
-  }) => Promise<void>;
+    category?: string;
+    tags?: string[];
+  }) => Promise<ImageImportFailure[]>;
+  createResourceFromImages: (paths: string[], name?: string) => Promise<ImageImportFailure[]>;
+  duplicateResource: (id: string, newName?: string) => Promise<ImageImportFailure[]>;
   deleteResource: (id: string) => Promise<void>;
   getResourceById: (id: string) => Resource | undefined;
   getResourceByName: (name: string) => Resource | undefined;
@@ -47,14 +66,37 @@ export const useResourceStore = create<ResourceState>((set, get) => ({
     }
   },
   createResource: async (payload) => {
-    const resource = await invoke<Resource>('create_resource', { payload });
+    const { resource, failedImages } = await invoke<ResourceImportResult>('create_resource', {
+      payload,
+    });
     set((state) => ({ resources: [...state.resources, resource] }));
+    return failedImages;
   },
   updateResource: async (payload) => {
-    const resource = await invoke<Resource>('update_resource', { payload });
+    const current = get().resources.find((r) => r.id === payload.id);
+    const { resource, failedImages } = await invoke<ResourceImportResult>('update_resource', {
+      payload: { ...payload, expectedUpdatedAt: current?.updatedAt ?? null },
+    });
     set((state) => ({
       resources: state.resources.map((r) => (r.id === resource.id ? resource : r)),
     }));
+    return failedImages;
+  },
+  createResourceFromImages: async (paths, name) => {
+    const { resource, failedImages } = await invoke<ResourceImportResult>(
+      'create_resource_from_images',
+      { paths, name: name ?? null }
+    );
+    set((state) => ({ resources: [...state.resources, resource] }));
+    return failedImages;
+  },
+  duplicateResource: async (id, newName) => {
+    const { resource, failedImages } = await invoke<ResourceImportResult>('duplicate_resource', {
+      id,
+      newName: newName ?? null,
+    });
+    set((state) => ({ resources: [...state.resources, resource] }));
+    return failedImages;
   },
   deleteResource: async (id) => {
     await invoke('delete_resource', { id });